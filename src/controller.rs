@@ -1,346 +1,1426 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
 
 use crate::{
-    init_folder,
-    model::{priority::Priority, task_state::TaskState},
-    view, ConcreteTaskBuilder, ConcreteTaskRelationalManager, Date, TaskBuilder,
-    TaskRelationalManager, DATABASE_PATH,
+    backup_database, init_folder,
+    model::{
+        critical_path, delimited,
+        error::TaskError,
+        filter,
+        location::{self, DatabaseSource},
+        name_policy::NamePolicy,
+        priority::Priority,
+        project::{self, Project},
+        query::{Column, Comparison, Condition, SortKey, TaskQuery},
+        report_column::{self, DEFAULT_COLUMNS},
+        subtask_policy::SubtaskPolicy,
+        tag,
+        task_state::TaskState,
+        taskwarrior::{self, TaskwarriorImport},
+        todotxt,
+    },
+    view::{self, GraphFormat, OutputFormat},
+    ConcreteTaskBuilder, ConcreteTaskRelationalManager, ConnectionOptions, Date, Task, TaskBuilder,
+    TaskRelationalManager, TaskUpdate,
 };
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 pub struct Cli {
+    #[arg(long, global = true, env = "TODO_LIST_DB", value_name = "PATH")]
+    pub db: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TODO_LIST_BUSY_TIMEOUT",
+        default_value_t = 5000,
+        value_name = "MILLISECONDS"
+    )]
+    pub busy_timeout: u64,
+
+    #[arg(long, global = true, env = "TODO_LIST_RETRIES", default_value_t = 3)]
+    pub retries: u32,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TODO_LIST_FORMAT",
+        value_enum,
+        default_value_t = OutputFormat::Table
+    )]
+    pub format: OutputFormat,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+impl Cli {
+    fn connection_options(&self) -> ConnectionOptions {
+        return ConnectionOptions {
+            busy_timeout: Duration::from_millis(self.busy_timeout),
+            retries: self.retries,
+            ..ConnectionOptions::default()
+        };
+    }
+}
+
+struct Storage {
+    db: Option<PathBuf>,
+    options: ConnectionOptions,
+}
+
+impl Storage {
+    fn open(&self) -> Result<ConcreteTaskRelationalManager, TaskError> {
+        let current_dir = env::current_dir()?;
+        let location = location::resolve(self.db.clone(), &current_dir).ok_or_else(|| {
+            TaskError::DatabaseNotFound(format!(
+                "{} or in its parents, and HOME is not set for the global one",
+                current_dir.display()
+            ))
+        })?;
+        if location.source == DatabaseSource::Global && !location.path.exists() {
+            return Err(TaskError::DatabaseNotFound(format!(
+                "{} and no {} in {} or its parents",
+                location.path.display(),
+                location::DATABASE_DIR_NAME,
+                current_dir.display()
+            )));
+        }
+        let conn = ConcreteTaskRelationalManager::new(&location.path, &self.options)?;
+        conn.migrate()?;
+        return Ok(conn);
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     Insert {
-        #[arg(short, long)]
-        name: Option<String>,
+        #[command(flatten)]
+        fields: NewTask,
+    },
 
-        #[arg(short, long)]
-        description: Option<String>,
+    Show {
+        #[command(flatten)]
+        key: TaskKey,
 
-        #[arg(short, long)]
-        term: Option<String>,
+        #[command(flatten)]
+        filters: Filters,
 
-        #[arg(short = 's', long)]
-        task_state: Option<String>,
+        #[arg(long, value_name = "COLUMN,...")]
+        columns: Option<String>,
 
-        #[arg(short, long)]
-        priority: Option<String>,
+        #[arg(long, conflicts_with = "TaskKey")]
+        by_project: bool,
+
+        #[arg(long, conflicts_with_all = ["TaskKey", "by_project"])]
+        tree: bool,
+
+        #[arg(long, requires = "tree")]
+        depth: Option<usize>,
     },
 
-    Show {
-        #[arg(short, long, conflicts_with = "id")]
-        name: Option<String>,
+    Ready {
+        #[command(flatten)]
+        filters: ReadyFilters,
 
-        #[arg(short, long, conflicts_with = "name")]
-        id: Option<u64>,
+        #[arg(long, value_name = "COLUMN,...")]
+        columns: Option<String>,
     },
 
     Modify {
-        #[arg(short, long, conflicts_with = "id")]
-        name: Option<String>,
+        #[command(flatten)]
+        key: TaskKey,
 
-        #[arg(short, long, conflicts_with = "name")]
-        id: Option<u64>,
+        #[arg(short = 'w', long = "where", value_name = "FILTER", conflicts_with_all = ["name", "id", "number", "all"])]
+        filter: Option<String>,
 
-        #[arg(short = 'N', long)]
-        new_name: Option<String>,
+        #[arg(long, requires = "name", conflicts_with_all = ["id", "number"])]
+        all: bool,
 
-        #[arg(short, long)]
-        description: Option<String>,
+        #[arg(long)]
+        force: bool,
 
-        #[arg(short, long)]
-        term: Option<String>,
+        #[command(flatten)]
+        changes: Changes,
+    },
+    Delete {
+        #[command(flatten)]
+        key: TaskKey,
+
+        #[arg(short = 'w', long = "where", value_name = "FILTER", conflicts_with_all = ["name", "id", "number", "all"])]
+        filter: Option<String>,
+
+        #[arg(long, requires = "name", conflicts_with_all = ["id", "number"])]
+        all: bool,
+
+        #[arg(long = "tag", value_name = "TAG", conflicts_with_all = ["name", "id", "number", "all"])]
+        tags: Vec<String>,
+    },
+    Search {
+        #[arg(required = true, num_args = 1..)]
+        words: Vec<String>,
+
+        #[arg(long, default_value_t = 20)]
+        limit: u64,
+    },
+    Compact,
+    Tags,
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommand,
+    },
+    Dependency {
+        #[command(subcommand)]
+        command: DependencyCommand,
+    },
+    Graph {
+        #[arg(value_enum, default_value_t = GraphFormat::Dot)]
+        language: GraphFormat,
 
-        #[arg(short = 's', long)]
-        task_state: Option<String>,
+        #[arg(long)]
+        all: bool,
+
+        #[arg(long, conflicts_with = "output")]
+        critical_path: bool,
+
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    Export {
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        #[arg(long, value_name = "COLUMN,...")]
+        columns: Option<String>,
+    },
+    Import {
+        #[arg(value_name = "FILE")]
+        file: Option<PathBuf>,
+
+        #[arg(long = "map", value_name = "FIELD=HEADER")]
+        mapping: Vec<String>,
+    },
+    Config {
+        name: String,
+
+        value: Option<String>,
+    },
+    Init {
+        #[arg(long)]
+        force: bool,
+
+        #[arg(long)]
+        global: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProjectCommand {
+    Create {
+        name: String,
+
+        #[arg(short, long)]
+        description: Option<String>,
 
         #[arg(short, long)]
         priority: Option<String>,
     },
+    List {
+        #[arg(long)]
+        all: bool,
+    },
+    Rename {
+        name: String,
+
+        new_name: String,
+    },
+    Archive {
+        name: String,
+    },
+    Unarchive {
+        name: String,
+    },
     Delete {
-        #[arg(short, long, conflicts_with = "id")]
-        name: Option<String>,
+        name: String,
+
+        #[arg(long, value_name = "PROJECT")]
+        reassign: Option<String>,
+
+        #[arg(long, conflicts_with = "reassign")]
+        unassign: bool,
+    },
+}
+
+// Tasks are given by number, the blocker must end before the blocked one
+// starts.
+#[derive(Subcommand, Debug)]
+pub enum DependencyCommand {
+    Add {
+        #[arg(value_name = "BLOCKER")]
+        blocker: u64,
 
-        #[arg(short, long, conflicts_with = "name")]
-        id: Option<u64>,
+        #[arg(value_name = "BLOCKED")]
+        blocked: u64,
     },
-    Init {},
+    Remove {
+        #[arg(value_name = "BLOCKER")]
+        blocker: u64,
+
+        #[arg(value_name = "BLOCKED")]
+        blocked: u64,
+    },
+    List,
 }
 
-fn insert(
+#[derive(Args, Debug)]
+pub struct NewTask {
+    #[arg(short, long)]
     name: Option<String>,
+
+    #[arg(short, long)]
     description: Option<String>,
+
+    #[arg(short, long)]
     term: Option<String>,
+
+    #[arg(short = 's', long)]
     task_state: Option<String>,
+
+    #[arg(short, long)]
     priority: Option<String>,
-) {
-    let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH);
 
-    let mut task_build = ConcreteTaskBuilder::new();
-    match name {
-        Some(i) => task_build = task_build.set_name(i),
-        None => {}
-    }
-    match description {
-        Some(i) => task_build = task_build.set_description(i),
-        None => {}
-    }
-    match term {
-        Some(i) => {
-            let date = Date::from_string(i);
-            match date {
-                Ok(j) => match j {
-                    Some(k) => task_build = task_build.set_term(k),
-                    None => {}
-                },
-                Err(err) => panic!("{}", err),
-            }
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+
+    #[arg(long, value_name = "PROJECT")]
+    project: Option<String>,
+
+    #[arg(long, value_name = "NUMBER")]
+    parent: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+#[group(multiple = false)]
+pub struct TaskKey {
+    #[arg(short, long)]
+    name: Option<String>,
+
+    #[arg(short, long)]
+    id: Option<u64>,
+
+    #[arg(short = 'k', long)]
+    number: Option<u64>,
+}
+
+impl TaskKey {
+    fn find(&self, conn: &ConcreteTaskRelationalManager) -> Result<Option<Task>, TaskError> {
+        if let Some(i) = &self.name {
+            return Ok(Some(conn.get_task_by_name(i)?));
         }
-        None => {}
-    }
-    match task_state {
-        Some(i) => match TaskState::from_string(&i) {
-            Some(j) => task_build = task_build.set_task_state(j),
-            None => {}
-        },
-        None => {}
-    }
-    match priority {
-        Some(i) => match Priority::from_string(&i) {
-            Some(j) => task_build = task_build.set_priority(j),
-            None => {}
-        },
-        None => {}
-    }
-    let task = task_build.get_task();
-    let mut result = conn.insert_task(&task);
-    loop {
-        match result {
-            Ok(()) => return,
-            Err(_) => result = conn.insert_task(&task),
+        if let Some(i) = self.id {
+            return Ok(Some(conn.get_task_by_id(i)?));
         }
+        if let Some(i) = self.number {
+            return Ok(Some(conn.get_task_by_number(i)?));
+        }
+        return Ok(None);
     }
 }
 
-fn show(name: Option<String>, id: Option<u64>) {
-    let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH);
+#[derive(Args, Debug)]
+#[group(multiple = true, conflicts_with = "TaskKey")]
+pub struct Filters {
+    #[arg(short = 'w', long = "where", value_name = "FILTER")]
+    filter: Option<String>,
 
-    if let Some(i) = name {
-        let task = conn.get_task_by_name(&i);
-        match task {
-            Some(i) => {
-                let tasks = vec![i];
-                view::show_query_tasks(&tasks);
-            }
-            None => {
-                println!("Doesent exists task with this name");
-            }
-        }
-        return;
-    }
+    #[arg(short = 's', long, value_name = "[OP]STATE")]
+    task_state: Option<String>,
 
-    if let Some(i) = id {
-        let task = conn.get_task_by_id(i);
-        match task {
-            Some(i) => {
-                let tasks = vec![i];
-                view::show_query_tasks(&tasks);
-            }
-            None => {
-                println!("Doesent exists task with this id");
-            }
+    #[arg(short, long, value_name = "[OP]PRIORITY")]
+    priority: Option<String>,
+
+    #[arg(long, value_name = "DATE")]
+    before: Option<String>,
+
+    #[arg(long, value_name = "DATE")]
+    after: Option<String>,
+
+    #[arg(long, value_name = "TEXT")]
+    search: Option<String>,
+
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+
+    #[arg(long, value_name = "PROJECT")]
+    project: Option<String>,
+
+    #[arg(long, value_name = "COLUMN[:asc|desc]")]
+    sort: Vec<String>,
+
+    #[arg(long)]
+    limit: Option<u64>,
+}
+
+// The part of the show filters that makes sense for tasks that are not
+// started, the state and the order are given by ready itself.
+#[derive(Args, Debug)]
+pub struct ReadyFilters {
+    #[arg(short = 'w', long = "where", value_name = "FILTER")]
+    filter: Option<String>,
+
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+
+    #[arg(long, value_name = "PROJECT")]
+    project: Option<String>,
+
+    #[arg(long)]
+    limit: Option<u64>,
+}
+
+impl ReadyFilters {
+    fn into_query(self) -> Result<TaskQuery, TaskError> {
+        let mut query = TaskQuery::new();
+        if let Some(i) = self.filter {
+            query = query.filter(filter::parse(&i)?);
+        }
+        for i in self.tags {
+            query = query.filter(Condition::HasTag(Some(tag::parse_tag(&i)?)));
         }
-        return;
+        if let Some(i) = self.project {
+            query = query.filter(project_condition(&i)?);
+        }
+        if let Some(i) = self.limit {
+            query = query.limit(i);
+        }
+        return Ok(query);
     }
+}
 
-    let tasks = conn.get_tasks();
-    match tasks {
-        Some(i) => {
-            view::show_query_tasks(&i);
+impl Filters {
+    fn into_query(self) -> Result<TaskQuery, TaskError> {
+        let mut query = TaskQuery::new();
+        if let Some(i) = self.filter {
+            query = query.filter(filter::parse(&i)?);
+        }
+        if let Some(i) = self.task_state {
+            query = query.filter(task_state_condition(i)?);
+        }
+        if let Some(i) = self.priority {
+            query = query.filter(priority_condition(i)?);
+        }
+        if let Some(i) = self.before {
+            query = query.filter(Condition::term(Comparison::Less, &parse_date(i)?));
+        }
+        if let Some(i) = self.after {
+            query = query.filter(Condition::term(Comparison::Greater, &parse_date(i)?));
+        }
+        if let Some(i) = self.search {
+            query = query.filter(Condition::Search(i));
+        }
+        for i in self.tags {
+            query = query.filter(Condition::HasTag(Some(tag::parse_tag(&i)?)));
         }
-        None => {
-            panic!("Problem with query the tasks");
+        if let Some(i) = self.project {
+            query = query.filter(project_condition(&i)?);
         }
+        for i in self.sort {
+            query = query.sort_by(SortKey::from_string(&i).ok_or(TaskError::InvalidSort(i))?);
+        }
+        if let Some(i) = self.limit {
+            query = query.limit(i);
+        }
+        return Ok(query);
     }
 }
 
-fn modify(
-    name: Option<String>,
-    id: Option<u64>,
+#[derive(Args, Debug)]
+pub struct Changes {
+    #[arg(short = 'N', long)]
     new_name: Option<String>,
+
+    #[arg(short, long)]
     description: Option<String>,
+
+    #[arg(long, conflicts_with = "description")]
+    no_description: bool,
+
+    #[arg(short, long)]
     term: Option<String>,
+
+    #[arg(long, conflicts_with = "term")]
+    no_term: bool,
+
+    #[arg(short = 's', long)]
     task_state: Option<String>,
+
+    #[arg(long, conflicts_with = "task_state")]
+    no_task_state: bool,
+
+    #[arg(short, long)]
     priority: Option<String>,
-) {
-    let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH);
-    let mut task_build = ConcreteTaskBuilder::new();
-    let mut task;
-    let mut init = false;
-    if let Some(i) = name.clone() {
-        task = conn.get_task_by_name(&i);
-        match task {
-            Some(j) => {
-                task_build = task_build.set_by_task(j);
-                init = true;
-            }
-            None => {
-                panic!("Dosent find task with this name");
-            }
+
+    #[arg(long, conflicts_with = "priority")]
+    no_priority: bool,
+
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+
+    #[arg(long = "untag", value_name = "TAG")]
+    untags: Vec<String>,
+
+    #[arg(long, value_name = "PROJECT")]
+    project: Option<String>,
+
+    #[arg(long, conflicts_with = "project")]
+    no_project: bool,
+
+    #[arg(long, value_name = "NUMBER")]
+    parent: Option<u64>,
+
+    #[arg(long, conflicts_with = "parent")]
+    no_parent: bool,
+}
+
+impl Changes {
+    fn into_update(self) -> Result<TaskUpdate, TaskError> {
+        let mut update = TaskUpdate::new();
+        if let Some(i) = self.new_name {
+            update = update.set_name(i);
         }
-    }
-    if let Some(i) = id {
-        task = conn.get_task_by_id(i);
-        match task {
-            Some(j) => {
-                task_build = task_build.set_by_task(j);
-                init = true;
-            }
-            None => {
-                panic!("Dosent find task with this name");
+        if let Some(i) = self.description {
+            update = update.set_description(Some(i));
+        }
+        if self.no_description {
+            update = update.set_description(None);
+        }
+        if let Some(i) = self.term {
+            if let Some(j) = parse_term(i)? {
+                update = update.set_term(Some(j));
             }
         }
+        if self.no_term {
+            update = update.set_term(None);
+        }
+        if let Some(i) = self.task_state {
+            update = update.set_task_state(Some(parse_task_state(i)?));
+        }
+        if self.no_task_state {
+            update = update.set_task_state(None);
+        }
+        if let Some(i) = self.priority {
+            update = update.set_priority(Some(parse_priority(i)?));
+        }
+        if self.no_priority {
+            update = update.set_priority(None);
+        }
+        for i in self.tags {
+            update = update.add_tag(tag::parse_tag(&i)?);
+        }
+        for i in self.untags {
+            update = update.remove_tag(tag::parse_tag(&i)?);
+        }
+        if let Some(i) = self.project {
+            update = update.set_project(Some(project::parse_project_name(&i)?));
+        }
+        if self.no_project {
+            update = update.set_project(None);
+        }
+        if self.no_parent {
+            update = update.set_parent(None);
+        }
+        return Ok(update);
     }
+}
 
-    if let Some(i) = new_name {
-        task_build = task_build.set_name(i);
+fn parse_term(term: String) -> Result<Option<Date>, TaskError> {
+    return Date::from_string(term.clone()).map_err(|_| TaskError::InvalidDate(term));
+}
+
+fn parse_date(date: String) -> Result<Date, TaskError> {
+    return match Date::from_string(date.clone()) {
+        Ok(Some(i)) => Ok(i),
+        _ => Err(TaskError::InvalidDate(date)),
+    };
+}
+
+// "none" matches the tasks where the field is not set.
+fn null_condition(column: Column, comparison: Comparison) -> Option<Condition> {
+    return match comparison {
+        Comparison::Equal => Some(Condition::IsNull {
+            column,
+            negated: false,
+        }),
+        Comparison::NotEqual => Some(Condition::IsNull {
+            column,
+            negated: true,
+        }),
+        _ => None,
+    };
+}
+
+fn task_state_condition(filter: String) -> Result<Condition, TaskError> {
+    let (comparison, value) = Comparison::split_prefix(&filter);
+    if value == "none" {
+        return null_condition(Column::TaskState, comparison)
+            .ok_or_else(|| TaskError::InvalidTaskState(filter.clone()));
     }
+    let task_state =
+        TaskState::from_string(value).ok_or_else(|| TaskError::InvalidTaskState(filter.clone()))?;
+    return Ok(Condition::task_state(comparison, &task_state));
+}
+
+fn priority_condition(filter: String) -> Result<Condition, TaskError> {
+    let (comparison, value) = Comparison::split_prefix(&filter);
+    if value == "none" {
+        return null_condition(Column::Priority, comparison)
+            .ok_or_else(|| TaskError::InvalidPriority(filter.clone()));
+    }
+    let priority =
+        Priority::from_string(value).ok_or_else(|| TaskError::InvalidPriority(filter.clone()))?;
+    return Ok(Condition::priority(comparison, &priority));
+}
+
+// "none" matches the tasks without a project, as in project:none.
+fn project_condition(filter: &str) -> Result<Condition, TaskError> {
+    if filter.trim().eq_ignore_ascii_case("none") {
+        return Ok(Condition::Not(Box::new(Condition::InProject(None))));
+    }
+    return Ok(Condition::InProject(Some(project::parse_project_name(
+        filter,
+    )?)));
+}
 
-    if let Some(i) = description {
+fn parse_task_state(task_state: String) -> Result<TaskState, TaskError> {
+    return TaskState::from_string(&task_state).ok_or(TaskError::InvalidTaskState(task_state));
+}
+
+fn parse_priority(priority: String) -> Result<Priority, TaskError> {
+    return Priority::from_string(&priority).ok_or(TaskError::InvalidPriority(priority));
+}
+
+fn insert(fields: NewTask, storage: &Storage) -> Result<(), TaskError> {
+    let tags = fields
+        .tags
+        .iter()
+        .map(|i| tag::parse_tag(i))
+        .collect::<Result<Vec<String>, TaskError>>()?;
+    let project = fields
+        .project
+        .map(|i| project::parse_project_name(&i))
+        .transpose()?;
+    let conn = storage.open()?;
+    let project = project.map(|i| conn.get_project(&i)).transpose()?;
+
+    let mut task_build = ConcreteTaskBuilder::new();
+    if let Some(i) = fields.name {
+        task_build = task_build.set_name(i);
+    }
+    if let Some(i) = fields.description {
         task_build = task_build.set_description(i);
     }
+    if let Some(i) = fields.term {
+        if let Some(j) = parse_term(i)? {
+            task_build = task_build.set_term(j);
+        }
+    }
+    if let Some(i) = fields.task_state {
+        task_build = task_build.set_task_state(parse_task_state(i)?);
+    }
+    // Without --priority the task takes the one of its project.
+    match (
+        fields.priority,
+        project.as_ref().and_then(|i| i.priority.clone()),
+    ) {
+        (Some(i), _) => task_build = task_build.set_priority(parse_priority(i)?),
+        (None, Some(i)) => task_build = task_build.set_priority(i),
+        (None, None) => {}
+    }
+    if let Some(i) = project {
+        task_build = task_build.set_project(i.name);
+    }
+    if let Some(i) = fields.parent {
+        task_build = task_build.set_parent(conn.get_task_by_number(i)?.get_id());
+    }
+    let task = task_build.set_tags(tags).get_task();
+    let number = conn.insert_task(&task)?;
+    println!("Task #{} created", number);
+    return Ok(());
+}
 
-    if let Some(i) = term {
-        match Date::from_string(i) {
-            Ok(j) => {
-                if let Some(k) = j {
-                    task_build = task_build.set_term(k);
-                }
+// Without --columns the table shows the columns saved with "config columns".
+fn show(
+    key: TaskKey,
+    filters: Filters,
+    columns: Option<String>,
+    by_project: bool,
+    tree: Option<Option<usize>>,
+    format: &OutputFormat,
+    storage: &Storage,
+) -> Result<(), TaskError> {
+    if by_project && *format != OutputFormat::Table {
+        return Err(TaskError::UnsupportedFormat {
+            command: "show --by-project",
+            format: String::from(format.to_str()),
+        });
+    }
+    if tree.is_some() && *format != OutputFormat::Table {
+        return Err(TaskError::UnsupportedFormat {
+            command: "show --tree",
+            format: String::from(format.to_str()),
+        });
+    }
+    let query = filters.into_query()?;
+    let columns = columns
+        .map(|i| report_column::parse_columns(&i))
+        .transpose()?;
+    let conn = storage.open()?;
+    assign_uuids(&conn, format)?;
+    let columns = match columns {
+        Some(i) => i,
+        None => conn.get_columns()?,
+    };
+    let tasks = match key.find(&conn)? {
+        Some(i) => vec![i],
+        None => conn.query_tasks(&query)?,
+    };
+    if by_project {
+        view::show_project_groups(&tasks, &columns);
+        return Ok(());
+    }
+    if let Some(depth) = tree {
+        view::show_task_tree(&tasks, &columns, depth);
+        return Ok(());
+    }
+    return view::show_tasks(&tasks, format, &columns);
+}
+
+fn modify(
+    key: TaskKey,
+    filter: Option<String>,
+    all: bool,
+    force: bool,
+    changes: Changes,
+    storage: &Storage,
+) -> Result<(), TaskError> {
+    let parent = changes.parent;
+    let mut update = changes.into_update()?;
+    let condition = filter.map(|i| filter::parse(&i)).transpose()?;
+    let conn = storage.open()?;
+    if let Some(i) = parent {
+        update = update.set_parent(Some(conn.get_task_by_number(i)?.get_id()));
+    }
+
+    if let Some(i) = condition {
+        let tasks = conn.query_tasks(&TaskQuery::new().filter(i))?;
+        check_blockers(&conn, &tasks, &update, force)?;
+        let ids: Vec<u64> = tasks.iter().map(|i| i.get_id()).collect();
+        let changed = conn.update_tasks(&ids, &update)?;
+        println!("Updated {} of the {} matching tasks", changed, ids.len());
+        return Ok(());
+    }
+
+    if let (true, Some(name)) = (all, &key.name) {
+        let tasks = conn.get_tasks_by_name(name)?;
+        check_blockers(&conn, &tasks, &update, force)?;
+        let ids: Vec<u64> = tasks.iter().map(|i| i.get_id()).collect();
+        let changed = conn.update_tasks(&ids, &update)?;
+        println!(
+            "Updated {} of the {} tasks named {:?}",
+            changed,
+            ids.len(),
+            name
+        );
+        return Ok(());
+    }
+
+    let task = key.find(&conn)?.ok_or(TaskError::MissingKey)?;
+    check_blockers(&conn, std::slice::from_ref(&task), &update, force)?;
+    if conn.update_task(task.get_id(), &update)? {
+        println!("Task #{} updated", task.get_number());
+    } else {
+        println!("Task #{} unchanged", task.get_number());
+    }
+    return Ok(());
+}
+
+// Starting a task whose blockers have not ended is refused, with --force it
+// goes ahead with a warning.
+fn check_blockers(
+    conn: &ConcreteTaskRelationalManager,
+    tasks: &[Task],
+    update: &TaskUpdate,
+    force: bool,
+) -> Result<(), TaskError> {
+    let in_progress = Some(&TaskState::InProgress);
+    for i in tasks {
+        if !i.is_blocked()
+            || i.task_state() == in_progress
+            || update.apply(i).task_state() != in_progress
+        {
+            continue;
+        }
+        let blockers = i
+            .blockers()
+            .iter()
+            .map(|j| conn.get_task_by_id(*j).map(|k| k.get_number()))
+            .collect::<Result<Vec<String>, TaskError>>()?;
+        if !force {
+            return Err(TaskError::Blocked {
+                task: i.get_number(),
+                blockers,
+            });
+        }
+        eprintln!(
+            "warning: starting task #{} while it is blocked by #{}",
+            i.get_number(),
+            blockers.join(", #")
+        );
+    }
+    return Ok(());
+}
+
+// Tasks not yet started with no open blocker, the most urgent first: by
+// priority, then by the nearest term.
+fn ready(
+    filters: ReadyFilters,
+    columns: Option<String>,
+    format: &OutputFormat,
+    storage: &Storage,
+) -> Result<(), TaskError> {
+    let query = filters
+        .into_query()?
+        .filter(Condition::Or(vec![
+            Condition::IsNull {
+                column: Column::TaskState,
+                negated: false,
+            },
+            Condition::task_state(Comparison::Equal, &TaskState::Pending),
+        ]))
+        .filter(Condition::Not(Box::new(Condition::Blocked)))
+        .sort_by(SortKey {
+            column: Column::Priority,
+            descending: true,
+        })
+        .sort_by(SortKey {
+            column: Column::Term,
+            descending: false,
+        });
+    let columns = columns
+        .map(|i| report_column::parse_columns(&i))
+        .transpose()?;
+    let conn = storage.open()?;
+    assign_uuids(&conn, format)?;
+    let columns = match columns {
+        Some(i) => i,
+        None => conn.get_columns()?,
+    };
+    return view::show_tasks(&conn.query_tasks(&query)?, format, &columns);
+}
+
+fn dependency_command(
+    command: DependencyCommand,
+    format: &OutputFormat,
+    storage: &Storage,
+) -> Result<(), TaskError> {
+    let conn = storage.open()?;
+    match command {
+        DependencyCommand::Add { blocker, blocked } => {
+            let (blocker_task, blocked_task) = (
+                conn.get_task_by_number(blocker)?,
+                conn.get_task_by_number(blocked)?,
+            );
+            if conn.add_dependency(blocker_task.get_id(), blocked_task.get_id())? {
+                println!("Task #{} now blocks #{}", blocker, blocked);
+            } else {
+                println!("Task #{} already blocks #{}", blocker, blocked);
+            }
+        }
+        DependencyCommand::Remove { blocker, blocked } => {
+            let (blocker_task, blocked_task) = (
+                conn.get_task_by_number(blocker)?,
+                conn.get_task_by_number(blocked)?,
+            );
+            if conn.remove_dependency(blocker_task.get_id(), blocked_task.get_id())? {
+                println!("Task #{} no longer blocks #{}", blocker, blocked);
+            } else {
+                println!("Task #{} did not block #{}", blocker, blocked);
             }
-            Err(err) => panic!("{}", err),
         }
+        DependencyCommand::List => list_dependencies(&conn, format)?,
     }
+    return Ok(());
+}
 
-    if let Some(i) = task_state {
-        match TaskState::from_string(&i) {
-            Some(j) => task_build = task_build.set_task_state(j),
-            None => panic!("Invalid Task State"),
+// One line per dependency, with the names and whether the blocker has
+// ended, aligned like the list of projects.
+fn list_dependencies(
+    conn: &ConcreteTaskRelationalManager,
+    format: &OutputFormat,
+) -> Result<(), TaskError> {
+    let mut dependencies = Vec::new();
+    for (blocker, blocked) in conn.list_dependencies()? {
+        dependencies.push((conn.get_task_by_id(blocker)?, conn.get_task_by_id(blocked)?));
+    }
+    if *format != OutputFormat::Table {
+        return view::write_dependencies(&dependencies, format);
+    }
+    let mut rows = Vec::new();
+    for (blocker, blocked) in dependencies {
+        rows.push(vec![
+            format!("#{}", blocker.get_number()),
+            blocker.get_name(),
+            format!("blocks #{}", blocked.get_number()),
+            blocked.get_name(),
+            String::from(if blocker.task_state() == Some(&TaskState::Ended) {
+                "done"
+            } else {
+                "open"
+            }),
+        ]);
+    }
+    print_aligned(&rows);
+    return Ok(());
+}
+
+// Without --all only the tasks with a dependency are drawn, and the critical
+// path is searched among them.
+fn graph(
+    language: GraphFormat,
+    all: bool,
+    critical_path: bool,
+    output: Option<PathBuf>,
+    format: &OutputFormat,
+    storage: &Storage,
+) -> Result<(), TaskError> {
+    let conn = storage.open()?;
+    let dependencies = conn.list_dependencies()?;
+    let tasks = conn
+        .query_tasks(&TaskQuery::new())?
+        .into_iter()
+        .filter(|i| {
+            all || dependencies
+                .iter()
+                .any(|(j, k)| *j == i.get_id() || *k == i.get_id())
+        })
+        .collect::<Vec<Task>>();
+    let path = critical_path::critical_path(&tasks, &dependencies, &Date::get_local_date());
+    if critical_path {
+        return view::show_critical_path(&tasks, path.as_ref(), format);
+    }
+    match output {
+        Some(i) => {
+            let mut out = BufWriter::new(File::create(i)?);
+            view::write_graph(&mut out, &language, &tasks, &dependencies, path.as_ref())?;
+            out.flush()?;
         }
+        None => view::write_graph(
+            &mut io::stdout().lock(),
+            &language,
+            &tasks,
+            &dependencies,
+            path.as_ref(),
+        )?,
     }
+    return Ok(());
+}
 
-    if let Some(i) = priority {
-        match Priority::from_string(&i) {
-            Some(j) => task_build = task_build.set_priority(j),
-            None => panic!("Invalid Priority"),
+// The tags and the filter must all match for a task to be deleted.
+fn delete(
+    key: TaskKey,
+    filter: Option<String>,
+    all: bool,
+    tags: Vec<String>,
+    storage: &Storage,
+) -> Result<(), TaskError> {
+    let mut conditions = Vec::new();
+    if let Some(i) = filter {
+        conditions.push(filter::parse(&i)?);
+    }
+    for i in tags {
+        conditions.push(Condition::HasTag(Some(tag::parse_tag(&i)?)));
+    }
+    let conn = storage.open()?;
+    if !conditions.is_empty() {
+        let query = conditions
+            .into_iter()
+            .fold(TaskQuery::new(), |query, i| query.filter(i));
+        let deleted = conn.delete_tasks(&query)?;
+        println!("Deleted {} tasks", deleted);
+        return Ok(());
+    }
+    if let Some(i) = key.name {
+        let deleted = conn.delete_task_by_name(&i, all)?;
+        if all {
+            println!("Deleted {} tasks named {:?}", deleted, i);
         }
+        return Ok(());
     }
+    let task = key.find(&conn)?.ok_or(TaskError::MissingKey)?;
+    return conn.delete_task_by_id(task.get_id());
+}
 
-    if !init {
-        panic!("Dosent have passed a key");
+fn search(
+    words: Vec<String>,
+    limit: u64,
+    format: &OutputFormat,
+    storage: &Storage,
+) -> Result<(), TaskError> {
+    let conn = storage.open()?;
+    let hits = conn.search_tasks(&words.join(" "), limit)?;
+    return view::show_search(&hits, format);
+}
+
+fn tags(format: &OutputFormat, storage: &Storage) -> Result<(), TaskError> {
+    let conn = storage.open()?;
+    let tags = conn.list_tags()?;
+    if *format != OutputFormat::Table {
+        return view::write_tags(&tags, format);
+    }
+    let width = tags
+        .iter()
+        .map(|(i, _)| i.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (i, count) in tags {
+        println!("{:width$}  {}", i, count, width = width);
     }
-    if let Some(i) = id {
-        let mut times = 0;
-        let mut result = conn.delete_task_by_id(i);
-        loop {
-            match result {
-                Ok(()) => break,
-                Err(_) => {
-                    if times > 100000 {
-                        panic!("Not capable to delete");
-                    }
-                    times += 1;
-                    result = conn.delete_task_by_id(i)
+    return Ok(());
+}
+
+fn project_command(
+    command: ProjectCommand,
+    format: &OutputFormat,
+    storage: &Storage,
+) -> Result<(), TaskError> {
+    match command {
+        ProjectCommand::Create {
+            name,
+            description,
+            priority,
+        } => {
+            let project = Project {
+                description,
+                priority: priority.map(parse_priority).transpose()?,
+                ..Project::new(project::parse_project_name(&name)?)
+            };
+            storage.open()?.create_project(&project)?;
+            println!("Project {:?} created", project.name);
+        }
+        ProjectCommand::List { all } => list_projects(all, format, storage)?,
+        ProjectCommand::Rename { name, new_name } => {
+            let new_name = project::parse_project_name(&new_name)?;
+            storage.open()?.rename_project(&name, &new_name)?;
+            println!("Project {:?} renamed to {:?}", name, new_name);
+        }
+        ProjectCommand::Archive { name } => {
+            storage.open()?.archive_project(&name, true)?;
+            println!("Project {:?} archived", name);
+        }
+        ProjectCommand::Unarchive { name } => {
+            storage.open()?.archive_project(&name, false)?;
+            println!("Project {:?} unarchived", name);
+        }
+        ProjectCommand::Delete {
+            name,
+            reassign,
+            unassign,
+        } => {
+            let reassign = match (reassign.as_deref(), unassign) {
+                (Some(i), _) => Some(Some(i)),
+                (None, true) => Some(None),
+                (None, false) => None,
+            };
+            let moved = storage.open()?.delete_project(&name, reassign)?;
+            match reassign {
+                Some(Some(i)) if moved > 0 => {
+                    println!(
+                        "Project {:?} deleted, {} tasks moved to {:?}",
+                        name, moved, i
+                    )
                 }
+                _ if moved > 0 => println!(
+                    "Project {:?} deleted, {} tasks left without a project",
+                    name, moved
+                ),
+                _ => println!("Project {:?} deleted", name),
             }
         }
     }
-    if let Some(i) = name {
-        let mut times = 0;
-        let mut result = conn.delete_task_by_name(&i);
-        loop {
-            match result {
-                Ok(()) => break,
-                Err(_) => {
-                    if times > 100000 {
-                        panic!("Not capable to delete");
-                    }
-                    times += 1;
-                    result = conn.delete_task_by_name(&i)
-                }
-            }
+    return Ok(());
+}
+
+// Every column but the last is padded to its widest value.
+fn print_aligned(rows: &[Vec<String>]) {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut widths = vec![0; columns.saturating_sub(1)];
+    for i in rows.iter() {
+        for (j, width) in widths.iter_mut().enumerate() {
+            *width = (*width).max(i[j].chars().count());
         }
     }
-    let mut times = 0;
-    let mut result = conn.insert_task(&task_build.get_task());
-    loop {
-        match result {
-            Ok(()) => break,
-            Err(_) => {
-                if times > 100000 {
-                    panic!("Not capable to delete");
-                }
-                times += 1;
-                result = conn.insert_task(&task_build.get_task());
-            }
+    for i in rows {
+        let line = i
+            .iter()
+            .zip(widths.iter().chain([&0]))
+            .map(|(j, width)| format!("{:width$}", j, width = width))
+            .collect::<Vec<String>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+}
+
+// One line per project with its progress, default priority and whether it
+// is archived, aligned like the list of tags.
+fn list_projects(all: bool, format: &OutputFormat, storage: &Storage) -> Result<(), TaskError> {
+    let conn = storage.open()?;
+    let projects = conn.list_projects(all)?;
+    if *format != OutputFormat::Table {
+        return view::write_projects(&projects, format);
+    }
+    let rows = projects
+        .iter()
+        .map(|i| {
+            vec![
+                i.project.name.clone(),
+                i.progress(),
+                i.project
+                    .priority
+                    .as_ref()
+                    .map(|j| j.to_string())
+                    .unwrap_or_else(|| String::from("None")),
+                String::from(if i.project.archived {
+                    "archived"
+                } else {
+                    "active"
+                }),
+                i.project.description.clone().unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<Vec<String>>>();
+    print_aligned(&rows);
+    return Ok(());
+}
+
+fn compact(storage: &Storage) -> Result<(), TaskError> {
+    let conn = storage.open()?;
+    let renumbered = conn.compact_numbers()?;
+    println!("Renumbered {} tasks", renumbered);
+    return Ok(());
+}
+
+// Taskwarrior needs a uuid for each task, given once so that the tasks of
+// every export can be imported back over the same ones.
+fn assign_uuids(
+    conn: &ConcreteTaskRelationalManager,
+    format: &OutputFormat,
+) -> Result<(), TaskError> {
+    if *format == OutputFormat::Taskwarrior {
+        conn.assign_uuids()?;
+    }
+    return Ok(());
+}
+
+fn report_taskwarrior(import: &TaskwarriorImport) {
+    if !import.ignored_fields.is_empty() {
+        let fields = import
+            .ignored_fields
+            .iter()
+            .map(|(i, j)| format!("{} ({} tasks)", i, j))
+            .collect::<Vec<String>>()
+            .join(", ");
+        eprintln!(
+            "warning: ignored the Taskwarrior fields without an equivalent: {}",
+            fields
+        );
+    }
+    if import.deleted > 0 {
+        eprintln!("warning: skipped {} deleted tasks", import.deleted);
+    }
+}
+
+// An export is meant to be read back, so it keeps every column unless
+// --columns asks for others.
+fn export(
+    output: Option<PathBuf>,
+    columns: Option<String>,
+    format: &OutputFormat,
+    storage: &Storage,
+) -> Result<(), TaskError> {
+    let columns = match columns {
+        Some(i) => report_column::parse_columns(&i)?,
+        None => DEFAULT_COLUMNS.to_vec(),
+    };
+    let conn = storage.open()?;
+    assign_uuids(&conn, format)?;
+    let tasks = conn.query_tasks(&TaskQuery::new())?;
+    match output {
+        Some(i) => {
+            let mut out = BufWriter::new(File::create(i)?);
+            view::write_tasks(&mut out, &tasks, format, &columns, "export")?;
+            out.flush()?;
         }
+        None => view::write_tasks(&mut io::stdout().lock(), &tasks, format, &columns, "export")?,
     }
+    return Ok(());
 }
 
-fn delete(name: Option<String>, id: Option<u64>) {
-    let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH);
-    if let Some(i) = name {
-        match conn.delete_task_by_name(&i) {
-            Ok(()) => {}
-            Err(err) => panic!("{}", err),
+// Rows that fail, including names refused by the unique policy, are listed
+// on stderr while the others are still imported. A task with the uuid of one
+// imported before updates it instead of adding a copy, and the projects that
+// do not exist yet are created.
+fn import(
+    file: Option<PathBuf>,
+    mapping: Vec<String>,
+    format: &OutputFormat,
+    storage: &Storage,
+) -> Result<(), TaskError> {
+    let mapping = mapping
+        .iter()
+        .map(|i| delimited::parse_mapping(i))
+        .collect::<Result<Vec<_>, TaskError>>()?;
+    let reader: Box<dyn BufRead> = match file {
+        Some(i) if i.as_os_str() != "-" => Box::new(BufReader::new(File::open(i)?)),
+        _ => Box::new(io::stdin().lock()),
+    };
+    if !mapping.is_empty() && format.delimiter().is_none() {
+        return Err(TaskError::InvalidImport(String::from(
+            "--map only applies to csv and tsv files",
+        )));
+    }
+    let (rows, mut errors) = match format {
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            delimited::read_tasks(reader, format.delimiter().unwrap_or(b','), &mapping)?
+        }
+        OutputFormat::Todotxt => todotxt::read_tasks(reader)?,
+        OutputFormat::Taskwarrior => {
+            let import = taskwarrior::read_tasks(reader)?;
+            report_taskwarrior(&import);
+            (import.rows, import.errors)
+        }
+        _ => {
+            return Err(TaskError::UnsupportedFormat {
+                command: "import",
+                format: String::from(format.to_str()),
+            })
+        }
+    };
+
+    let conn = storage.open()?;
+    for i in rows.iter().filter_map(|i| i.task.project()) {
+        match conn.get_project(i) {
+            Ok(_) => {}
+            Err(TaskError::ProjectNotFound(_)) => {
+                conn.create_project(&Project::new(String::from(i)))?
+            }
+            Err(j) => return Err(j),
         }
     }
-    if let Some(i) = id {
-        match conn.delete_task_by_id(i) {
+    let mut imported = 0;
+    let mut updated = 0;
+    for i in rows {
+        let existing = match i.task.uuid() {
+            Some(j) => match conn.get_task_by_uuid(j) {
+                Ok(k) => Some(k),
+                Err(TaskError::NotFound(_)) => None,
+                Err(k) => return Err(k),
+            },
+            None => None,
+        };
+        let result = match existing {
+            Some(j) => conn
+                .update_task(j.get_id(), &TaskUpdate::from_task(&i.task))
+                .map(|changed| updated += changed as usize),
+            None => conn.insert_task(&i.task).map(|_| imported += 1),
+        };
+        match result {
             Ok(()) => {}
-            Err(err) => panic!("{}", err),
+            Err(j @ (TaskError::DuplicateName(_) | TaskError::ArchivedProject(_))) => {
+                errors.push(delimited::RowError {
+                    row: i.row,
+                    message: j.to_string(),
+                })
+            }
+            Err(j) => return Err(j),
         }
     }
+    errors.sort_by_key(|i| i.row);
+    for i in errors.iter() {
+        eprintln!("{}", i);
+    }
+    if !errors.is_empty() {
+        return Err(TaskError::PartialImport {
+            imported,
+            skipped: errors.len(),
+        });
+    }
+    if updated > 0 {
+        println!("Imported {} tasks and updated {}", imported, updated);
+    } else {
+        println!("Imported {} tasks", imported);
+    }
+    return Ok(());
 }
 
-fn init() {
-    init_folder();
-    let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH);
-    conn.init_db();
+fn config(name: String, value: Option<String>, storage: &Storage) -> Result<(), TaskError> {
+    let conn = storage.open()?;
+    match (name.as_str(), value) {
+        ("name-policy", None) => println!("{}", conn.get_name_policy()?),
+        ("name-policy", Some(i)) => {
+            let policy = NamePolicy::from_string(&i).ok_or(TaskError::InvalidSetting {
+                name: name.clone(),
+                value: Some(i),
+            })?;
+            conn.set_name_policy(&policy)?;
+            println!("{} set to {}", name, policy);
+        }
+        ("subtask-policy", None) => println!("{}", conn.get_subtask_policy()?),
+        ("subtask-policy", Some(i)) => {
+            let policy = SubtaskPolicy::from_string(&i).ok_or(TaskError::InvalidSetting {
+                name: name.clone(),
+                value: Some(i),
+            })?;
+            conn.set_subtask_policy(&policy)?;
+            println!("{} set to {}", name, policy);
+        }
+        ("columns", None) => println!("{}", report_column::columns_to_string(&conn.get_columns()?)),
+        ("columns", Some(i)) => {
+            let columns = report_column::parse_columns(&i)?;
+            conn.set_columns(&columns)?;
+            println!(
+                "{} set to {}",
+                name,
+                report_column::columns_to_string(&columns)
+            );
+        }
+        _ => return Err(TaskError::InvalidSetting { name, value: None }),
+    }
+    return Ok(());
 }
 
-pub fn exec() {
-    match Cli::parse().command {
-        Commands::Insert {
-            name,
-            description,
-            term,
-            task_state,
-            priority,
-        } => {
-            insert(name, description, term, task_state, priority);
+fn init(force: bool, global: bool, storage: &Storage) -> Result<(), TaskError> {
+    let path = if global {
+        location::global_database_path().ok_or_else(|| {
+            TaskError::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "neither XDG_DATA_HOME nor HOME is set",
+            ))
+        })?
+    } else {
+        match &storage.db {
+            Some(i) => i.clone(),
+            None => location::local_database_path(&env::current_dir()?),
         }
+    };
 
-        Commands::Show { name, id } => {
-            show(name, id);
+    if path.exists() {
+        if !force {
+            let conn = ConcreteTaskRelationalManager::new(&path, &storage.options)?;
+            let found = conn.schema_version()?;
+            let version = conn.migrate()?;
+            let path = fs::canonicalize(&path)?;
+            if found == version {
+                println!(
+                    "Database already initialised at {} (schema version {})",
+                    path.display(),
+                    version
+                );
+            } else {
+                println!(
+                    "Database already initialised at {}, upgraded from schema version {} to {}",
+                    path.display(),
+                    found,
+                    version
+                );
+            }
+            return Ok(());
         }
+        let backup = backup_database(&path)?;
+        println!("Moved the old database to {}", backup.display());
+    }
+
+    init_folder(&path)?;
+    let options = ConnectionOptions {
+        create: true,
+        ..storage.options.clone()
+    };
+    let conn = ConcreteTaskRelationalManager::new(&path, &options)?;
+    let version = conn.init_db()?;
+    println!(
+        "Initialised database at {} (schema version {})",
+        fs::canonicalize(&path)?.display(),
+        version
+    );
+    return Ok(());
+}
+
+pub fn exec() -> ExitCode {
+    let cli = Cli::parse();
+    let storage = Storage {
+        options: cli.connection_options(),
+        db: cli.db,
+    };
+    let format = cli.format;
+    let result = match cli.command {
+        Commands::Insert { fields } => insert(fields, &storage),
+        Commands::Show {
+            key,
+            filters,
+            columns,
+            by_project,
+            tree,
+            depth,
+        } => show(
+            key,
+            filters,
+            columns,
+            by_project,
+            tree.then_some(depth),
+            &format,
+            &storage,
+        ),
         Commands::Modify {
-            name,
-            id,
-            new_name,
-            description,
-            term,
-            task_state,
-            priority,
-        } => {
-            modify(name, id, new_name, description, term, task_state, priority);
+            key,
+            filter,
+            all,
+            force,
+            changes,
+        } => modify(key, filter, all, force, changes, &storage),
+        Commands::Ready { filters, columns } => ready(filters, columns, &format, &storage),
+        Commands::Delete {
+            key,
+            filter,
+            all,
+            tags,
+        } => delete(key, filter, all, tags, &storage),
+        Commands::Search { words, limit } => search(words, limit, &format, &storage),
+        Commands::Compact => compact(&storage),
+        Commands::Tags => tags(&format, &storage),
+        Commands::Project { command } => project_command(command, &format, &storage),
+        Commands::Dependency { command } => dependency_command(command, &format, &storage),
+        Commands::Graph {
+            language,
+            all,
+            critical_path,
+            output,
+        } => graph(language, all, critical_path, output, &format, &storage),
+        Commands::Export { output, columns } => export(output, columns, &format, &storage),
+        Commands::Import { file, mapping } => import(file, mapping, &format, &storage),
+        Commands::Config { name, value } => config(name, value, &storage),
+        Commands::Init { force, global } => init(force, global, &storage),
+    };
+
+    return match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(err.exit_code())
         }
-        Commands::Delete { name, id } => delete(name, id),
-        Commands::Init {} => init(),
-    }
+    };
 }
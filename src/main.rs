@@ -1,3 +1,5 @@
+#![allow(clippy::needless_return)]
+
 mod controller;
 mod model;
 mod view;
@@ -6,9 +8,8 @@ use crate::model::date::Date;
 use crate::model::priority::Priority;
 use crate::model::task::*;
 use crate::model::task_state::TaskState;
-use std::error::Error;
+use std::process::ExitCode;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    controller::exec();
-    return Ok(());
+fn main() -> ExitCode {
+    return controller::exec();
 }
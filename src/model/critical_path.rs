@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use crate::{Date, Task, TaskState};
+
+// Tasks have no duration, so each open task counts as one day of work and
+// the path is the chain of open tasks that leaves the least time before the
+// term of its last task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CriticalPath {
+    // Ids from the first task to start to the one with the term.
+    pub tasks: Vec<u64>,
+    pub days_left: i64,
+    pub slack: i64,
+}
+
+impl CriticalPath {
+    pub fn at_risk(&self) -> bool {
+        return self.slack < 0;
+    }
+}
+
+// Dependencies are pairs of blocker and blocked ids. Ended tasks are done
+// work and left out of the chains. On a tie the task given first wins.
+pub fn critical_path(
+    tasks: &[Task],
+    dependencies: &[(u64, u64)],
+    today: &Date,
+) -> Option<CriticalPath> {
+    let open = tasks
+        .iter()
+        .filter(|i| i.task_state() != Some(&TaskState::Ended))
+        .collect::<Vec<&Task>>();
+    let is_open = |id: &u64| open.iter().any(|i| i.get_id() == *id);
+    let mut blockers: HashMap<u64, Vec<u64>> = HashMap::new();
+    for (blocker, blocked) in dependencies {
+        if is_open(blocker) && is_open(blocked) {
+            blockers.entry(*blocked).or_default().push(*blocker);
+        }
+    }
+    let mut chains = HashMap::new();
+    let mut critical: Option<CriticalPath> = None;
+    for i in open {
+        let Some(days_left) = i.term().and_then(|j| today.days_until(j)) else {
+            continue;
+        };
+        let chain = longest_chain(i.get_id(), &blockers, &mut chains);
+        let slack = days_left - (chain.len() as i64 - 1);
+        if critical.as_ref().is_none_or(|j| slack < j.slack) {
+            critical = Some(CriticalPath {
+                tasks: chain,
+                days_left,
+                slack,
+            });
+        }
+    }
+    return critical;
+}
+
+// The longest chain of blockers that ends at the task. The chain is stored
+// before its blockers are walked so a cycle cannot recurse forever.
+fn longest_chain(
+    task_id: u64,
+    blockers: &HashMap<u64, Vec<u64>>,
+    chains: &mut HashMap<u64, Vec<u64>>,
+) -> Vec<u64> {
+    if let Some(i) = chains.get(&task_id) {
+        return i.clone();
+    }
+    chains.insert(task_id, vec![task_id]);
+    let mut longest = Vec::new();
+    for i in blockers.get(&task_id).into_iter().flatten() {
+        let chain = longest_chain(*i, blockers, chains);
+        if chain.len() > longest.len() {
+            longest = chain;
+        }
+    }
+    longest.push(task_id);
+    chains.insert(task_id, longest.clone());
+    return longest;
+}
+
+#[cfg(test)]
+mod tests_critical_path {
+    use super::*;
+    use crate::{ConcreteTaskBuilder, TaskBuilder};
+
+    fn date(day: u8, month: u8) -> Date {
+        return Date {
+            day,
+            month,
+            year: 2026,
+        };
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let design = ConcreteTaskBuilder::new().get_task();
+        let build = ConcreteTaskBuilder::new().get_task();
+        let docs = ConcreteTaskBuilder::new().set_term(date(20, 11)).get_task();
+        let release = ConcreteTaskBuilder::new().set_term(date(5, 11)).get_task();
+        let dependencies = [
+            (design.get_id(), build.get_id()),
+            (build.get_id(), release.get_id()),
+            (design.get_id(), docs.get_id()),
+        ];
+        let tasks = vec![design.clone(), build.clone(), docs, release.clone()];
+
+        let path = critical_path(&tasks, &dependencies, &date(1, 11)).unwrap();
+
+        assert_eq!(
+            path.tasks,
+            vec![design.get_id(), build.get_id(), release.get_id()]
+        );
+        assert_eq!((path.days_left, path.slack), (4, 2));
+        assert!(!path.at_risk());
+
+        let late = critical_path(&tasks, &dependencies, &date(4, 11)).unwrap();
+        assert_eq!(late.slack, -1);
+        assert!(late.at_risk());
+    }
+
+    #[test]
+    fn test_ended_tasks_are_done_work() {
+        let build = ConcreteTaskBuilder::new()
+            .set_task_state(TaskState::Ended)
+            .get_task();
+        let release = ConcreteTaskBuilder::new().set_term(date(1, 11)).get_task();
+        let dependencies = [(build.get_id(), release.get_id())];
+        let tasks = vec![build, release.clone()];
+
+        let path = critical_path(&tasks, &dependencies, &date(1, 11)).unwrap();
+
+        assert_eq!(path.tasks, vec![release.get_id()]);
+        assert_eq!(path.slack, 0);
+        assert_eq!(critical_path(&tasks[..1], &[], &date(1, 11)), None);
+    }
+}
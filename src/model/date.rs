@@ -1,7 +1,11 @@
-use chrono::{Datelike, Local, NaiveDate};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Serialize, Serializer};
 use std::error::Error;
 use std::fmt::Display;
 
+const UTC_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
 #[derive(Debug)]
 pub struct Date {
     pub day: u8,
@@ -30,65 +34,96 @@ impl Date {
             Err(_) => Err("invalid date".into()),
         };
     }
+    pub fn from_iso_string(date: &str) -> Result<Self, Box<dyn Error>> {
+        return match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(i) => Ok(Date {
+                day: i.day() as u8,
+                month: i.month() as u8,
+                year: i.year(),
+            }),
+            Err(_) => Err("invalid date".into()),
+        };
+    }
+
+    pub fn to_iso_string(&self) -> String {
+        return format!("{:04}-{:02}-{:02}", self.year, self.month, self.day);
+    }
+
+    // The local midnight of the date in UTC, written like 20260301T030000Z
+    // as iCalendar and Taskwarrior expect.
+    pub fn to_utc_timestamp(&self) -> String {
+        let midnight = NaiveDate::from_ymd_opt(self.year, self.month as u32, self.day as u32)
+            .and_then(|i| i.and_hms_opt(0, 0, 0))
+            .and_then(|i| Local.from_local_datetime(&i).earliest());
+        return match midnight {
+            Some(i) => i
+                .with_timezone(&Utc)
+                .format(UTC_TIMESTAMP_FORMAT)
+                .to_string(),
+            None => format!("{:04}{:02}{:02}T000000Z", self.year, self.month, self.day),
+        };
+    }
+
+    pub fn from_utc_timestamp(timestamp: &str) -> Option<Self> {
+        let instant = NaiveDateTime::parse_from_str(timestamp, UTC_TIMESTAMP_FORMAT).ok()?;
+        let local = Utc.from_utc_datetime(&instant).with_timezone(&Local);
+        return Some(Date {
+            day: local.day() as u8,
+            month: local.month() as u8,
+            year: local.year(),
+        });
+    }
+
+    // Days from this date to the other one, negative when the other comes first.
+    pub fn days_until(&self, other: &Date) -> Option<i64> {
+        let from = NaiveDate::from_ymd_opt(self.year, self.month as u32, self.day as u32)?;
+        let to = NaiveDate::from_ymd_opt(other.year, other.month as u32, other.day as u32)?;
+        return Some((to - from).num_days());
+    }
+
     pub fn get_local_date() -> Self {
         let actual_date = Local::now();
         return Date {
             day: actual_date.day() as u8,
             month: actual_date.month() as u8,
-            year: actual_date.year() as i32,
+            year: actual_date.year(),
         };
     }
 }
 
-impl PartialEq for Date {
-    fn eq(&self, other: &Self) -> bool {
-        return self.day == other.day && self.month == other.month && self.year == other.year;
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_str(&self.to_iso_string());
     }
 }
 
-impl PartialOrd for Date {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self < other {
-            return Some(std::cmp::Ordering::Less);
-        }
-        if self == other {
-            return Some(std::cmp::Ordering::Equal);
-        }
-        return Some(std::cmp::Ordering::Greater);
-    }
-
-    fn lt(&self, other: &Self) -> bool {
-        if self.year < other.year {
-            return true;
-        }
-        if self.year == other.year && self.month < other.month {
-            return true;
-        }
-        if self.year == other.year && self.month == other.month && self.day < other.day {
-            return true;
-        }
-        return false;
+impl ToSql for Date {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        return Ok(ToSqlOutput::from(self.to_iso_string()));
     }
+}
 
-    fn gt(&self, other: &Self) -> bool {
-        if self.year > other.year {
-            return true;
-        }
-        if self.year == other.year && self.month > other.month {
-            return true;
-        }
-        if self.year == other.year && self.month == other.month && self.day > other.day {
-            return true;
-        }
-        return false;
+impl FromSql for Date {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let date = value.as_str()?;
+        return Date::from_iso_string(date)
+            .map_err(|_| FromSqlError::Other(format!("invalid date {:?}", date).into()));
     }
+}
 
-    fn le(&self, other: &Self) -> bool {
-        return self == other || self < other;
+impl PartialEq for Date {
+    fn eq(&self, other: &Self) -> bool {
+        return self.day == other.day && self.month == other.month && self.year == other.year;
     }
+}
 
-    fn ge(&self, other: &Self) -> bool {
-        return self == other || self > other;
+impl PartialOrd for Date {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        return (self.year, self.month, self.day).partial_cmp(&(
+            other.year,
+            other.month,
+            other.day,
+        ));
     }
 }
 
@@ -104,6 +139,7 @@ impl Clone for Date {
 }
 
 #[cfg(test)]
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
 mod tests_date {
     use super::*;
 
@@ -407,4 +443,37 @@ mod tests_date {
         assert!(a.is_ok(), "Result should be not a error");
         assert_eq!(a.unwrap(), None);
     }
+
+    #[test]
+    fn iso_round_trip() {
+        let a = Date {
+            day: 3,
+            month: 7,
+            year: 2024,
+        };
+        assert_eq!(a.to_iso_string(), String::from("2024-07-03"));
+        assert_eq!(Date::from_iso_string("2024-07-03").unwrap(), a);
+        assert!(Date::from_iso_string("03-07-2024").is_err());
+    }
+
+    #[test]
+    fn utc_timestamp_round_trip() {
+        let a = Date {
+            day: 1,
+            month: 3,
+            year: 2026,
+        };
+        assert_eq!(Date::from_utc_timestamp(&a.to_utc_timestamp()), Some(a));
+        assert_eq!(Date::from_utc_timestamp("2026-03-01"), None);
+    }
+
+    #[test]
+    fn test_days_until() {
+        let a = Date::from_iso_string("2026-02-27").unwrap();
+        let b = Date::from_iso_string("2026-03-02").unwrap();
+
+        assert_eq!(a.days_until(&b), Some(3));
+        assert_eq!(b.days_until(&a), Some(-3));
+        assert_eq!(a.days_until(&a), Some(0));
+    }
 }
@@ -0,0 +1,361 @@
+use std::fmt::Display;
+use std::io::{self, Read, Write};
+
+use crate::model::error::TaskError;
+use crate::model::project;
+use crate::model::query::Column;
+use crate::model::tag;
+use crate::{ConcreteTaskBuilder, Date, Priority, Task, TaskBuilder, TaskState};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    pub row: usize,
+    pub message: String,
+}
+
+impl Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "row {}: {}", self.row, self.message)
+    }
+}
+
+// Rows are counted like a spreadsheet does, the header is row 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedRow {
+    pub row: usize,
+    pub task: Task,
+}
+
+pub fn write_records<W: Write>(
+    writer: W,
+    delimiter: u8,
+    header: &[&str],
+    records: &[Vec<String>],
+) -> Result<(), TaskError> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(writer);
+    writer.write_record(header).map_err(io::Error::from)?;
+    for i in records {
+        writer.write_record(i).map_err(io::Error::from)?;
+    }
+    writer.flush()?;
+    return Ok(());
+}
+
+// Parses "field=Header", the field is one of the names accepted by the filter
+// language and the header is the title of the column in the file.
+pub fn parse_mapping(mapping: &str) -> Result<(Column, String), TaskError> {
+    let invalid = || {
+        return TaskError::InvalidImport(format!(
+            "invalid mapping {:?}, expected FIELD=HEADER such as term=\"Due date\"",
+            mapping
+        ));
+    };
+    let (field, header) = mapping.split_once('=').ok_or_else(invalid)?;
+    let column = Column::from_string(&field.trim().to_lowercase()).ok_or_else(invalid)?;
+    if !importable(&column) {
+        return Err(TaskError::InvalidImport(format!(
+            "the {} field cannot be imported, new tasks get their own",
+            column.to_str()
+        )));
+    }
+    return Ok((column, String::from(header.trim())));
+}
+
+// The number, the id and the modification date of an imported task are given
+// by this database, as a file copied from another one would collide.
+fn importable(column: &Column) -> bool {
+    return matches!(
+        column,
+        Column::Name | Column::Description | Column::Term | Column::TaskState | Column::Priority
+    );
+}
+
+// "Task State" and "task_state" both name the same column.
+fn header_column(header: &str) -> Option<Column> {
+    return Column::from_string(&header.trim().to_lowercase().replace(' ', "_"));
+}
+
+fn find_columns(
+    headers: &csv::StringRecord,
+    mapping: &[(Column, String)],
+) -> Result<Vec<(Column, usize)>, TaskError> {
+    let mut columns: Vec<(Column, usize)> = Vec::new();
+    for (column, header) in mapping {
+        let position = headers
+            .iter()
+            .position(|i| i.trim().eq_ignore_ascii_case(header))
+            .ok_or_else(|| {
+                TaskError::InvalidImport(format!("there is no column named {:?}", header))
+            })?;
+        columns.push((column.clone(), position));
+    }
+    for (position, header) in headers.iter().enumerate() {
+        if let Some(column) = header_column(header) {
+            let mapped = columns.iter().any(|(i, j)| *i == column || *j == position);
+            if importable(&column) && !mapped {
+                columns.push((column, position));
+            }
+        }
+    }
+    if !columns.iter().any(|(i, _)| *i == Column::Name) {
+        return Err(TaskError::InvalidImport(String::from(
+            "there is no Name column, map one with --map name=HEADER",
+        )));
+    }
+    return Ok(columns);
+}
+
+// Export writes absent fields as empty cells, so only those are absent, a
+// name or description of "None" is text like any other.
+fn optional(value: &str) -> Option<&str> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    return Some(value);
+}
+
+fn read_task(
+    record: &csv::StringRecord,
+    columns: &[(Column, usize)],
+    tags: Option<usize>,
+    project: Option<usize>,
+) -> Result<Task, String> {
+    let mut builder = ConcreteTaskBuilder::new();
+    for (column, position) in columns {
+        let value = match optional(record.get(*position).unwrap_or("")) {
+            Some(i) => i,
+            None if *column == Column::Name => return Err(String::from("the name is empty")),
+            None => continue,
+        };
+        builder = match column {
+            Column::Name => builder.set_name(String::from(value)),
+            Column::Description => builder.set_description(String::from(value)),
+            Column::Term => {
+                let date = match Date::from_string(String::from(value)) {
+                    Ok(Some(i)) => Ok(i),
+                    _ => Date::from_iso_string(value),
+                };
+                match date {
+                    Ok(i) => builder.set_term(i),
+                    Err(_) => {
+                        return Err(format!(
+                            "invalid date {:?}, expected dd-mm-yyyy or yyyy-mm-dd",
+                            value
+                        ))
+                    }
+                }
+            }
+            Column::TaskState => match TaskState::from_string(&value.to_lowercase()) {
+                Some(i) => builder.set_task_state(i),
+                None => {
+                    return Err(format!(
+                        "unknown task state {:?}, expected pending, in progress or ended",
+                        value
+                    ))
+                }
+            },
+            Column::Priority => match Priority::from_string(&value.to_lowercase()) {
+                Some(i) => builder.set_priority(i),
+                None => {
+                    return Err(format!(
+                        "unknown priority {:?}, expected low, normal, high or urgent",
+                        value
+                    ))
+                }
+            },
+            _ => builder,
+        };
+    }
+    if let Some(i) = tags.and_then(|i| optional(record.get(i).unwrap_or(""))) {
+        builder = builder.set_tags(tag::parse_tags(i).map_err(|err| err.to_string())?);
+    }
+    if let Some(i) = project.and_then(|i| optional(record.get(i).unwrap_or(""))) {
+        builder =
+            builder.set_project(project::parse_project_name(i).map_err(|err| err.to_string())?);
+    }
+    return Ok(builder.get_task());
+}
+
+// A bad row is reported and skipped so the rest of the file is still read,
+// only a file that cannot be read at all fails as a whole.
+pub fn read_tasks<R: Read>(
+    reader: R,
+    delimiter: u8,
+    mapping: &[(Column, String)],
+) -> Result<(Vec<ImportedRow>, Vec<RowError>), TaskError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(reader);
+    let headers = reader.headers().map_err(io::Error::from)?.clone();
+    let columns = find_columns(&headers, mapping)?;
+    // Tags and projects are not columns of the filter language, so only the
+    // columns with those titles are read.
+    let tags = headers
+        .iter()
+        .position(|i| i.trim().eq_ignore_ascii_case("tags"));
+    let project = headers
+        .iter()
+        .position(|i| i.trim().eq_ignore_ascii_case("project"));
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let row = i + 2;
+        let record = match record {
+            Ok(j) => j,
+            Err(j) if j.is_io_error() => return Err(TaskError::Io(io::Error::from(j))),
+            Err(j) => {
+                errors.push(RowError {
+                    row,
+                    message: j.to_string(),
+                });
+                continue;
+            }
+        };
+        if record.iter().all(|j| j.trim().is_empty()) {
+            continue;
+        }
+        match read_task(&record, &columns, tags, project) {
+            Ok(task) => rows.push(ImportedRow { row, task }),
+            Err(message) => errors.push(RowError { row, message }),
+        }
+    }
+    return Ok((rows, errors));
+}
+
+#[cfg(test)]
+mod tests_delimited {
+    use super::*;
+
+    const HEADER: [&str; 10] = [
+        "#",
+        "ID",
+        "Name",
+        "Modification",
+        "Term",
+        "Task State",
+        "Priority",
+        "Description",
+        "Tags",
+        "Project",
+    ];
+
+    #[test]
+    fn test_write_quotes_commas_and_newlines() {
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Deploy"))
+            .set_description(String::from("build, test\nand \"ship\""))
+            .set_priority(Priority::High)
+            .get_task();
+        let mut out = Vec::new();
+
+        write_records(&mut out, b',', &HEADER, &[task.to_record()]).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "#,ID,Name,Modification,Term,Task State,Priority,Description,Tags,Project\n,{},Deploy,{},,,high,\"build, test\nand \"\"ship\"\"\",,\n",
+                task.get_id(),
+                Date::get_local_date().to_iso_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_read_what_was_written() {
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Deploy"))
+            .set_description(String::from("tab\there, comma, and\nnewline"))
+            .set_term(Date::from_iso_string("2025-03-01").unwrap())
+            .set_task_state(TaskState::InProgress)
+            .set_tags(vec![String::from("ops"), String::from("ui")])
+            .set_project(String::from("Mobile app"))
+            .get_task();
+        for delimiter in [b',', b'\t'] {
+            let mut out = Vec::new();
+            write_records(&mut out, delimiter, &HEADER, &[task.to_record()]).unwrap();
+
+            let (rows, errors) = read_tasks(out.as_slice(), delimiter, &[]).unwrap();
+
+            assert!(errors.is_empty());
+            assert_eq!(rows.len(), 1);
+            let imported = &rows[0].task;
+            assert_ne!(imported.get_id(), task.get_id(), "Imports get a new id");
+            assert_eq!(imported.to_vec()[2..], task.to_vec()[2..]);
+        }
+    }
+
+    #[test]
+    fn test_none_is_text() {
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("None"))
+            .set_description(String::from("None"))
+            .get_task();
+        let mut out = Vec::new();
+        write_records(&mut out, b',', &HEADER, &[task.to_record()]).unwrap();
+
+        let (rows, errors) = read_tasks(out.as_slice(), b',', &[]).unwrap();
+
+        assert!(errors.is_empty());
+        let imported = &rows[0].task;
+        assert_eq!(imported.get_name(), String::from("None"));
+        assert_eq!(imported.description(), Some("None"));
+    }
+
+    #[test]
+    fn test_bad_rows_are_reported_and_skipped() {
+        let file = "Name,Term,Priority\nDeploy,01-02-2025,High\nTest,32-13-2025,low\n,,\nShip,,hihg\n\"Review\",2025-02-01,\n";
+
+        let (rows, errors) = read_tasks(file.as_bytes(), b',', &[]).unwrap();
+
+        assert_eq!(
+            rows.iter().map(|i| i.row).collect::<Vec<usize>>(),
+            vec![2, 6],
+            "Blank rows are ignored"
+        );
+        assert_eq!(rows[0].task.get_priority(), String::from("high"));
+        assert_eq!(rows[1].task.get_term(), String::from("1-2-2025"));
+        assert_eq!(
+            errors,
+            vec![
+                RowError {
+                    row: 3,
+                    message: String::from(
+                        "invalid date \"32-13-2025\", expected dd-mm-yyyy or yyyy-mm-dd"
+                    ),
+                },
+                RowError {
+                    row: 5,
+                    message: String::from(
+                        "unknown priority \"hihg\", expected low, normal, high or urgent"
+                    ),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mapping() {
+        let file = "Title;Due date;Name\nDeploy;2025-03-01;ignored\n";
+        let mapping = vec![
+            parse_mapping("name=Title").unwrap(),
+            parse_mapping("term = due date").unwrap(),
+        ];
+
+        let (rows, _) = read_tasks(file.as_bytes(), b';', &mapping).unwrap();
+
+        assert_eq!(rows[0].task.get_name(), String::from("Deploy"));
+        assert_eq!(rows[0].task.get_term(), String::from("1-3-2025"));
+        assert!(parse_mapping("name").is_err());
+        assert!(parse_mapping("color=Colour").is_err());
+        assert!(parse_mapping("id=Key").is_err(), "Ids are not imported");
+        assert!(matches!(
+            read_tasks("Title\nDeploy\n".as_bytes(), b',', &[]),
+            Err(TaskError::InvalidImport(_))
+        ));
+    }
+}
@@ -0,0 +1,397 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::model::report_column;
+
+#[derive(Debug)]
+pub enum TaskError {
+    NotFound(String),
+    AmbiguousName {
+        name: String,
+        candidates: Vec<u64>,
+    },
+    InvalidDate(String),
+    InvalidPriority(String),
+    InvalidTaskState(String),
+    InvalidSetting {
+        name: String,
+        value: Option<String>,
+    },
+    DuplicateName(String),
+    InvalidSort(String),
+    InvalidQuery {
+        query: String,
+        position: usize,
+        message: String,
+    },
+    UnsupportedFormat {
+        command: &'static str,
+        format: String,
+    },
+    InvalidImport(String),
+    InvalidColumn(String),
+    InvalidTag(String),
+    InvalidProject(String),
+    ProjectNotFound(String),
+    DuplicateProject(String),
+    ArchivedProject(String),
+    ProjectNotEmpty {
+        name: String,
+        tasks: usize,
+    },
+    SubtaskCycle {
+        task: String,
+        parent: String,
+    },
+    HasSubtasks {
+        task: String,
+        subtasks: usize,
+        ending: bool,
+    },
+    DependencyCycle {
+        blocker: String,
+        blocked: String,
+    },
+    Blocked {
+        task: String,
+        blockers: Vec<String>,
+    },
+    PartialImport {
+        imported: usize,
+        skipped: usize,
+    },
+    MissingKey,
+    DatabaseNotFound(String),
+    Busy(u32),
+    Storage(rusqlite::Error),
+    Io(std::io::Error),
+    Migration {
+        version: u32,
+        description: &'static str,
+        source: rusqlite::Error,
+    },
+    SchemaMismatch {
+        found: u32,
+        supported: u32,
+    },
+}
+
+impl TaskError {
+    // Scripts branch on these values, so a code must never be reused for a
+    // different kind of failure. 1 is left for panics and 2 matches the usage
+    // errors reported by clap.
+    pub fn exit_code(&self) -> u8 {
+        return match self {
+            Self::MissingKey => 2,
+            Self::NotFound(_) => 3,
+            Self::AmbiguousName { .. } => 4,
+            Self::InvalidDate(_) => 5,
+            Self::InvalidPriority(_) => 6,
+            Self::InvalidTaskState(_) => 7,
+            Self::Storage(_) | Self::Io(_) | Self::Migration { .. } => 8,
+            Self::SchemaMismatch { .. } => 9,
+            Self::DatabaseNotFound(_) => 10,
+            Self::Busy(_) => 11,
+            Self::InvalidSetting { .. } => 12,
+            Self::DuplicateName(_) => 13,
+            Self::InvalidSort(_) => 14,
+            Self::InvalidQuery { .. } => 15,
+            Self::UnsupportedFormat { .. } => 16,
+            Self::InvalidImport(_) => 17,
+            Self::PartialImport { .. } => 18,
+            Self::InvalidColumn(_) => 19,
+            Self::InvalidTag(_) => 20,
+            Self::InvalidProject(_) => 21,
+            Self::ProjectNotFound(_) => 22,
+            Self::DuplicateProject(_) => 23,
+            Self::ArchivedProject(_) => 24,
+            Self::ProjectNotEmpty { .. } => 25,
+            Self::SubtaskCycle { .. } => 26,
+            Self::HasSubtasks { .. } => 27,
+            Self::DependencyCycle { .. } => 28,
+            Self::Blocked { .. } => 29,
+        };
+    }
+}
+
+impl Display for TaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            Self::NotFound(key) => write!(f, "there is no task with {}", key),
+            Self::AmbiguousName { name, candidates } => write!(
+                f,
+                "the name {:?} matches {} tasks: {}",
+                name,
+                candidates.len(),
+                candidates
+                    .iter()
+                    .map(|i| format!("#{}", i))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Self::InvalidDate(date) => {
+                write!(f, "invalid date {:?}, expected dd-mm-yyyy", date)
+            }
+            Self::InvalidPriority(priority) => write!(
+                f,
+                "invalid priority {:?}, expected low, normal, high or urgent",
+                priority
+            ),
+            Self::InvalidTaskState(state) => write!(
+                f,
+                "invalid task state {:?}, expected pending, in progress or ended",
+                state
+            ),
+            Self::InvalidSetting { name, value: None } => {
+                write!(f, "unknown setting {:?}", name)
+            }
+            Self::InvalidSetting {
+                name,
+                value: Some(value),
+            } => write!(f, "invalid value {:?} for the setting {:?}", value, name),
+            Self::DuplicateName(name) => write!(
+                f,
+                "there is already a task named {:?} and the name policy is unique",
+                name
+            ),
+            Self::InvalidSort(sort) => write!(
+                f,
+                "invalid sort {:?}, expected a column such as term or priority:desc",
+                sort
+            ),
+            Self::InvalidQuery {
+                query,
+                position,
+                message,
+            } => write!(
+                f,
+                "invalid query, {}\n  {}\n  {}^",
+                message,
+                query,
+                " ".repeat(*position)
+            ),
+            Self::UnsupportedFormat { command, format } => {
+                write!(f, "{} does not support the {} format", command, format)
+            }
+            Self::InvalidImport(message) => write!(f, "cannot import the file, {}", message),
+            Self::InvalidTag(tag) => write!(
+                f,
+                "invalid tag {:?}, expected letters, digits, - or _",
+                tag
+            ),
+            Self::InvalidProject(name) => write!(
+                f,
+                "invalid project name {:?}, it cannot be empty, none or have control characters",
+                name
+            ),
+            Self::ProjectNotFound(name) => write!(
+                f,
+                "there is no project named {:?}, create it with project create",
+                name
+            ),
+            Self::DuplicateProject(name) => {
+                write!(f, "there is already a project named {:?}", name)
+            }
+            Self::ArchivedProject(name) => write!(
+                f,
+                "the project {:?} is archived, unarchive it to add tasks",
+                name
+            ),
+            Self::ProjectNotEmpty { name, tasks } => write!(
+                f,
+                "the project {:?} has {} tasks, pass --reassign with another project or --unassign",
+                name, tasks
+            ),
+            Self::SubtaskCycle { task, parent } => write!(
+                f,
+                "task #{} cannot be a subtask of #{}, it would be its own ancestor",
+                task, parent
+            ),
+            Self::HasSubtasks {
+                task,
+                subtasks,
+                ending: true,
+            } => write!(
+                f,
+                "task #{} has {} subtasks that are not ended and the subtask policy is refuse",
+                task, subtasks
+            ),
+            Self::HasSubtasks {
+                task,
+                subtasks,
+                ending: false,
+            } => write!(
+                f,
+                "task #{} has {} subtasks and the subtask policy is refuse",
+                task, subtasks
+            ),
+            Self::DependencyCycle { blocker, blocked } => write!(
+                f,
+                "task #{} cannot block #{}, it would end up waiting on itself",
+                blocker, blocked
+            ),
+            Self::Blocked { task, blockers } => write!(
+                f,
+                "task #{} is blocked by #{}, pass --force to start it anyway",
+                task,
+                blockers.join(", #")
+            ),
+            Self::InvalidColumn(column) => write!(
+                f,
+                "unknown column {:?}, expected a list of {}",
+                column,
+                report_column::column_names()
+            ),
+            Self::PartialImport { imported, skipped } => write!(
+                f,
+                "imported {} tasks but skipped {} rows with errors",
+                imported, skipped
+            ),
+            Self::MissingKey => write!(
+                f,
+                "pass the --name, the --id, the --number or a --where filter"
+            ),
+            Self::DatabaseNotFound(path) => write!(
+                f,
+                "there is no database at {}, run the init command first",
+                path
+            ),
+            Self::Busy(attempts) => write!(
+                f,
+                "the database is locked by another process, gave up after {} attempts",
+                attempts
+            ),
+            Self::Storage(err) => write!(f, "database error: {}", err),
+            Self::Io(err) => write!(f, "file system error: {}", err),
+            Self::Migration {
+                version,
+                description,
+                source,
+            } => write!(
+                f,
+                "upgrading the database to schema version {} ({}) failed: {}",
+                version, description, source
+            ),
+            Self::SchemaMismatch { found, supported } => write!(
+                f,
+                "the database schema version is {} but this binary only understands up to version {}, update todo_list to open it",
+                found, supported
+            ),
+        };
+    }
+}
+
+impl Error for TaskError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        return match self {
+            Self::Storage(err) => Some(err),
+            Self::Migration { source, .. } => Some(source),
+            Self::Io(err) => Some(err),
+            _ => None,
+        };
+    }
+}
+
+impl From<rusqlite::Error> for TaskError {
+    fn from(err: rusqlite::Error) -> Self {
+        return Self::Storage(err);
+    }
+}
+
+impl From<std::io::Error> for TaskError {
+    fn from(err: std::io::Error) -> Self {
+        return Self::Io(err);
+    }
+}
+
+#[cfg(test)]
+mod tests_error {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let errors = [
+            TaskError::MissingKey,
+            TaskError::NotFound(String::from("id 1")),
+            TaskError::AmbiguousName {
+                name: String::from("a"),
+                candidates: vec![1, 2],
+            },
+            TaskError::InvalidDate(String::from("a")),
+            TaskError::InvalidPriority(String::from("a")),
+            TaskError::InvalidTaskState(String::from("a")),
+            TaskError::Storage(rusqlite::Error::InvalidQuery),
+            TaskError::SchemaMismatch {
+                found: 3,
+                supported: 2,
+            },
+            TaskError::DatabaseNotFound(String::from("a")),
+            TaskError::Busy(3),
+            TaskError::InvalidSetting {
+                name: String::from("a"),
+                value: None,
+            },
+            TaskError::DuplicateName(String::from("a")),
+            TaskError::InvalidSort(String::from("a")),
+            TaskError::InvalidQuery {
+                query: String::from("a"),
+                position: 0,
+                message: String::from("a"),
+            },
+            TaskError::UnsupportedFormat {
+                command: "import",
+                format: String::from("table"),
+            },
+            TaskError::InvalidImport(String::from("a")),
+            TaskError::PartialImport {
+                imported: 1,
+                skipped: 1,
+            },
+            TaskError::InvalidColumn(String::from("a")),
+            TaskError::InvalidTag(String::from("a")),
+            TaskError::InvalidProject(String::from("a")),
+            TaskError::ProjectNotFound(String::from("a")),
+            TaskError::DuplicateProject(String::from("a")),
+            TaskError::ArchivedProject(String::from("a")),
+            TaskError::ProjectNotEmpty {
+                name: String::from("a"),
+                tasks: 1,
+            },
+            TaskError::SubtaskCycle {
+                task: String::from("1"),
+                parent: String::from("2"),
+            },
+            TaskError::HasSubtasks {
+                task: String::from("1"),
+                subtasks: 2,
+                ending: false,
+            },
+            TaskError::DependencyCycle {
+                blocker: String::from("1"),
+                blocked: String::from("2"),
+            },
+            TaskError::Blocked {
+                task: String::from("1"),
+                blockers: vec![String::from("2")],
+            },
+        ];
+        let mut codes: Vec<u8> = errors.iter().map(|i| i.exit_code()).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len(), "Each kind should have its code");
+        assert!(!codes.contains(&0), "An error should never exit with 0");
+        assert!(!codes.contains(&1), "1 is reserved for panics");
+    }
+
+    #[test]
+    fn test_ambiguous_message_lists_candidates() {
+        let err = TaskError::AmbiguousName {
+            name: String::from("Deploy"),
+            candidates: vec![10, 20],
+        };
+        assert_eq!(
+            err.to_string(),
+            String::from("the name \"Deploy\" matches 2 tasks: #10, #20")
+        );
+    }
+}
@@ -0,0 +1,522 @@
+use rusqlite::types::Value;
+
+use crate::model::error::TaskError;
+use crate::model::project;
+use crate::model::query::{Column, Comparison, Condition};
+use crate::model::tag;
+use crate::Date;
+use crate::Priority;
+use crate::TaskState;
+
+// Grammar of a filter, "and" binds tighter than "or":
+//   expression := term ("or" term)*
+//   term       := factor ("and" factor)*
+//   factor     := "not" factor | "(" expression ")" | field operator value
+//   operator   := ":" | "=" | "!=" | "<" | "<=" | ">" | ">=" | "~"
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Operator(String),
+    Open,
+    Close,
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Lexeme {
+    token: Token,
+    position: usize,
+}
+
+const OPERATORS: [&str; 8] = [">=", "<=", "!=", ":", "=", "<", ">", "~"];
+
+fn is_word_char(i: char) -> bool {
+    return !i.is_whitespace() && !"():=!<>~\"".contains(i);
+}
+
+fn tokenize(query: &str) -> Result<Vec<Lexeme>, TaskError> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut lexemes = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let position = i;
+        let rest: String = chars[i..].iter().collect();
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if chars[i] == '(' || chars[i] == ')' {
+            let token = if chars[i] == '(' {
+                Token::Open
+            } else {
+                Token::Close
+            };
+            lexemes.push(Lexeme { token, position });
+            i += 1;
+            continue;
+        }
+        if let Some(j) = OPERATORS.iter().find(|j| rest.starts_with(**j)) {
+            lexemes.push(Lexeme {
+                token: Token::Operator(String::from(*j)),
+                position,
+            });
+            i += j.len();
+            continue;
+        }
+        if chars[i] == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(query_error(query, position, "unterminated quote")),
+                    Some('"') => break,
+                    Some('\\') if i + 1 < chars.len() => {
+                        text.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(j) => {
+                        text.push(*j);
+                        i += 1;
+                    }
+                }
+            }
+            lexemes.push(Lexeme {
+                token: Token::Text(text),
+                position,
+            });
+            i += 1;
+            continue;
+        }
+        if !is_word_char(chars[i]) {
+            return Err(query_error(
+                query,
+                position,
+                format!("unexpected character {:?}", chars[i]).as_str(),
+            ));
+        }
+        let mut word = String::new();
+        while i < chars.len() && is_word_char(chars[i]) {
+            word.push(chars[i]);
+            i += 1;
+        }
+        lexemes.push(Lexeme {
+            token: Token::Word(word),
+            position,
+        });
+    }
+    lexemes.push(Lexeme {
+        token: Token::End,
+        position: chars.len(),
+    });
+    return Ok(lexemes);
+}
+
+fn query_error(query: &str, position: usize, message: &str) -> TaskError {
+    return TaskError::InvalidQuery {
+        query: String::from(query),
+        position,
+        message: String::from(message),
+    };
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    lexemes: Vec<Lexeme>,
+    current: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> &Lexeme {
+        return &self.lexemes[self.current];
+    }
+
+    fn advance(&mut self) -> Lexeme {
+        let lexeme = self.lexemes[self.current].clone();
+        if lexeme.token != Token::End {
+            self.current += 1;
+        }
+        return lexeme;
+    }
+
+    fn error(&self, position: usize, message: &str) -> TaskError {
+        return query_error(self.query, position, message);
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        return matches!(&self.peek().token, Token::Word(i) if i.eq_ignore_ascii_case(keyword));
+    }
+
+    fn expression(&mut self) -> Result<Condition, TaskError> {
+        let mut conditions = vec![self.term()?];
+        while self.peek_keyword("or") {
+            self.advance();
+            conditions.push(self.term()?);
+        }
+        return Ok(combine(conditions, Condition::Or));
+    }
+
+    fn term(&mut self) -> Result<Condition, TaskError> {
+        let mut conditions = vec![self.factor()?];
+        while self.peek_keyword("and") {
+            self.advance();
+            conditions.push(self.factor()?);
+        }
+        return Ok(combine(conditions, Condition::And));
+    }
+
+    fn factor(&mut self) -> Result<Condition, TaskError> {
+        if self.peek_keyword("not") {
+            self.advance();
+            return Ok(Condition::Not(Box::new(self.factor()?)));
+        }
+        if self.peek().token == Token::Open {
+            self.advance();
+            let condition = self.expression()?;
+            let close = self.advance();
+            if close.token != Token::Close {
+                return Err(self.error(close.position, "expected a closing parenthesis"));
+            }
+            return Ok(condition);
+        }
+        return self.comparison();
+    }
+
+    fn comparison(&mut self) -> Result<Condition, TaskError> {
+        let field = self.advance();
+        // Tags and projects live in their own tables, so they are the fields
+        // without a column.
+        let column = match &field.token {
+            Token::Word(i) if i.eq_ignore_ascii_case("tag") => Field::Tag,
+            Token::Word(i) if i.eq_ignore_ascii_case("project") => Field::Project,
+            Token::Word(i) => Field::Column(Column::from_string(&i.to_lowercase()).ok_or_else(|| {
+                self.error(
+                    field.position,
+                    format!(
+                        "unknown field {:?}, expected name, description, state, priority, term, modification, number, id, tag or project",
+                        i
+                    )
+                    .as_str(),
+                )
+            })?),
+            _ => {
+                return Err(self.error(
+                    field.position,
+                    "expected a condition such as state:pending",
+                ))
+            }
+        };
+
+        let operator = self.advance();
+        let operator_text = match &operator.token {
+            Token::Operator(i) => i.clone(),
+            _ => {
+                return Err(self.error(
+                    operator.position,
+                    "expected an operator such as :, != or >= after the field",
+                ))
+            }
+        };
+
+        let value = self.advance();
+        let (text, quoted) = match &value.token {
+            Token::Word(i) => (i.clone(), false),
+            Token::Text(i) => (i.clone(), true),
+            _ => return Err(self.error(value.position, "expected a value")),
+        };
+
+        let condition = match column {
+            Field::Column(i) => build_condition(i, &operator_text, &text, quoted),
+            i => build_set_condition(&i, &operator_text, &text, quoted),
+        };
+        return condition.map_err(|i| self.error(value.position, &i));
+    }
+}
+
+enum Field {
+    Column(Column),
+    Tag,
+    Project,
+}
+
+fn combine(mut conditions: Vec<Condition>, group: fn(Vec<Condition>) -> Condition) -> Condition {
+    if conditions.len() == 1 {
+        return conditions.remove(0);
+    }
+    return group(conditions);
+}
+
+fn build_condition(
+    column: Column,
+    operator: &str,
+    text: &str,
+    quoted: bool,
+) -> Result<Condition, String> {
+    let comparison = match operator {
+        ":" | "=" | "~" => Comparison::Equal,
+        "!=" => Comparison::NotEqual,
+        "<" => Comparison::Less,
+        "<=" => Comparison::LessEqual,
+        ">" => Comparison::Greater,
+        ">=" => Comparison::GreaterEqual,
+        _ => return Err(format!("unknown operator {}", operator)),
+    };
+    let operator_error = || {
+        return format!(
+            "the operator {} cannot be used with {}",
+            operator,
+            column.to_str()
+        );
+    };
+    let is_text = matches!(column, Column::Name | Column::Description);
+    let is_equality = comparison == Comparison::Equal || comparison == Comparison::NotEqual;
+
+    if operator == "~" {
+        if !is_text {
+            return Err(operator_error());
+        }
+        return Ok(Condition::Contains {
+            column,
+            text: String::from(text),
+        });
+    }
+
+    let nullable = matches!(
+        column,
+        Column::Description | Column::Term | Column::TaskState | Column::Priority
+    );
+    if nullable && !quoted && text.eq_ignore_ascii_case("none") {
+        if !is_equality {
+            return Err(operator_error());
+        }
+        return Ok(Condition::IsNull {
+            column,
+            negated: comparison == Comparison::NotEqual,
+        });
+    }
+
+    return match column {
+        Column::Name | Column::Description => {
+            if !is_equality {
+                return Err(operator_error());
+            }
+            Ok(Condition::Compare {
+                column,
+                comparison,
+                value: Value::Text(String::from(text)),
+            })
+        }
+        Column::TaskState => match TaskState::from_string(text) {
+            Some(i) => Ok(Condition::task_state(comparison, &i)),
+            None => Err(format!(
+                "unknown state {:?}, expected pending, \"in progress\", ended or none",
+                text
+            )),
+        },
+        Column::Priority => match Priority::from_string(text) {
+            Some(i) => Ok(Condition::priority(comparison, &i)),
+            None => Err(format!(
+                "unknown priority {:?}, expected low, normal, high, urgent or none",
+                text
+            )),
+        },
+        Column::Term | Column::Modification => {
+            let date = match Date::from_string(String::from(text)) {
+                Ok(Some(i)) => Ok(i),
+                _ => Date::from_iso_string(text),
+            };
+            match date {
+                Ok(i) => Ok(Condition::date(column, comparison, &i)),
+                Err(_) => Err(format!(
+                    "invalid date {:?}, expected dd-mm-yyyy or yyyy-mm-dd",
+                    text
+                )),
+            }
+        }
+        Column::Number | Column::Id => match text.parse::<u64>() {
+            Ok(i) => Ok(Condition::Compare {
+                column,
+                comparison,
+                value: Value::Integer(i as i64),
+            }),
+            Err(_) => Err(format!("expected a whole number, found {:?}", text)),
+        },
+    };
+}
+
+// tag:none finds the tasks without tags and tag!=none the ones with any,
+// project:none and project!=none do the same with projects.
+fn build_set_condition(
+    field: &Field,
+    operator: &str,
+    text: &str,
+    quoted: bool,
+) -> Result<Condition, String> {
+    let (name, condition): (&str, fn(Option<String>) -> Condition) = match field {
+        Field::Project => ("project", Condition::InProject),
+        _ => ("tag", Condition::HasTag),
+    };
+    let negated = match operator {
+        ":" | "=" => false,
+        "!=" => true,
+        _ => {
+            return Err(format!(
+                "the operator {} cannot be used with {}",
+                operator, name
+            ))
+        }
+    };
+    let (condition, negated) = if !quoted && text.eq_ignore_ascii_case("none") {
+        (condition(None), !negated)
+    } else {
+        let value = match field {
+            Field::Project => project::parse_project_name(text).map_err(|i| i.to_string())?,
+            _ => tag::parse_tag(text).map_err(|i| i.to_string())?,
+        };
+        (condition(Some(value)), negated)
+    };
+    if negated {
+        return Ok(Condition::Not(Box::new(condition)));
+    }
+    return Ok(condition);
+}
+
+pub fn parse(query: &str) -> Result<Condition, TaskError> {
+    let mut parser = Parser {
+        query,
+        lexemes: tokenize(query)?,
+        current: 0,
+    };
+    let condition = parser.expression()?;
+    let rest = parser.advance();
+    if rest.token != Token::End {
+        return Err(parser.error(rest.position, "expected and, or or the end of the filter"));
+    }
+    return Ok(condition);
+}
+
+#[cfg(test)]
+mod tests_filter {
+    use super::*;
+
+    fn position(result: Result<Condition, TaskError>) -> usize {
+        return match result {
+            Err(TaskError::InvalidQuery { position, .. }) => position,
+            other => panic!("Expected an invalid query, got {:?}", other),
+        };
+    }
+
+    #[test]
+    fn test_and_binds_tighter_than_or() {
+        let condition =
+            parse("state:pending and priority>=high and term<2026-12-01 or name~deploy").unwrap();
+        assert_eq!(
+            condition,
+            Condition::Or(vec![
+                Condition::And(vec![
+                    Condition::task_state(Comparison::Equal, &TaskState::Pending),
+                    Condition::priority(Comparison::GreaterEqual, &Priority::High),
+                    Condition::term(
+                        Comparison::Less,
+                        &Date::from_iso_string("2026-12-01").unwrap()
+                    ),
+                ]),
+                Condition::Contains {
+                    column: Column::Name,
+                    text: String::from("deploy"),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parenthesis_not_and_quotes() {
+        let condition = parse("not (state:\"in progress\" OR priority:none)").unwrap();
+        assert_eq!(
+            condition,
+            Condition::Not(Box::new(Condition::Or(vec![
+                Condition::task_state(Comparison::Equal, &TaskState::InProgress),
+                Condition::IsNull {
+                    column: Column::Priority,
+                    negated: false,
+                },
+            ])))
+        );
+        assert_eq!(
+            parse("name:\"none\"").unwrap(),
+            Condition::Compare {
+                column: Column::Name,
+                comparison: Comparison::Equal,
+                value: Value::Text(String::from("none")),
+            },
+            "A quoted none is a name"
+        );
+        assert_eq!(
+            parse("term>=1-2-2025").unwrap(),
+            Condition::term(
+                Comparison::GreaterEqual,
+                &Date::from_iso_string("2025-02-01").unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_error_positions() {
+        assert_eq!(position(parse("state:pendin")), 6, "Points at the value");
+        assert_eq!(position(parse("color:red")), 0, "Points at the field");
+        assert_eq!(position(parse("state pending")), 6);
+        assert_eq!(position(parse("state:pending priority:high")), 14);
+        assert_eq!(position(parse("(state:pending")), 14);
+        assert_eq!(position(parse("name:\"open")), 5);
+        assert_eq!(position(parse("priority~high")), 9);
+        assert_eq!(position(parse("")), 0);
+        assert_eq!(position(parse("tag:a,b")), 4);
+        assert_eq!(position(parse("tag>ui")), 4);
+    }
+
+    #[test]
+    fn test_tags() {
+        let ui = Condition::HasTag(Some(String::from("ui")));
+        assert_eq!(parse("tag:UI").unwrap(), ui);
+        assert_eq!(
+            parse("Tag!=ui").unwrap(),
+            Condition::Not(Box::new(ui.clone()))
+        );
+        assert_eq!(
+            parse("tag:none").unwrap(),
+            Condition::Not(Box::new(Condition::HasTag(None)))
+        );
+        assert_eq!(parse("tag!=none").unwrap(), Condition::HasTag(None));
+        assert_eq!(
+            parse("tag:\"none\"").unwrap(),
+            Condition::HasTag(Some(String::from("none")))
+        );
+    }
+
+    #[test]
+    fn test_projects() {
+        let backend = Condition::InProject(Some(String::from("Mobile app")));
+        assert_eq!(parse("project:\"Mobile app\"").unwrap(), backend);
+        assert_eq!(
+            parse("Project!=\"Mobile app\"").unwrap(),
+            Condition::Not(Box::new(backend))
+        );
+        assert_eq!(
+            parse("project:none").unwrap(),
+            Condition::Not(Box::new(Condition::InProject(None)))
+        );
+        assert_eq!(parse("project!=none").unwrap(), Condition::InProject(None));
+        assert_eq!(position(parse("project~api")), 8);
+    }
+
+    #[test]
+    fn test_error_message_has_caret() {
+        let err = parse("priority>=hihg").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            String::from(
+                "invalid query, unknown priority \"hihg\", expected low, normal, high, urgent or none\n  priority>=hihg\n            ^"
+            )
+        );
+    }
+}
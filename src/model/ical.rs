@@ -0,0 +1,166 @@
+use std::io::Write;
+
+use crate::model::error::TaskError;
+use crate::{Priority, Task, TaskState};
+
+const MAX_LINE_OCTETS: usize = 75;
+
+// The uid only depends on the id of the task, so a calendar that imports the
+// file again replaces its entries instead of adding new ones.
+fn task_uid(task: &Task) -> String {
+    return format!("task-{}@todo_list", task.get_id());
+}
+
+fn status(task_state: &TaskState) -> &'static str {
+    return match task_state {
+        TaskState::Pending => "NEEDS-ACTION",
+        TaskState::InProgress => "IN-PROCESS",
+        TaskState::Ended => "COMPLETED",
+    };
+}
+
+// RFC 5545 ranks 1 as the highest priority and 9 as the lowest, 5 is medium.
+fn priority(priority: &Priority) -> u8 {
+    return match priority {
+        Priority::Urgent => 1,
+        Priority::High => 3,
+        Priority::Normal => 5,
+        Priority::Low => 9,
+    };
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for i in text.chars() {
+        match i {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(i);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(i),
+        }
+    }
+    return escaped;
+}
+
+// Lines longer than 75 octets continue on the next one after a space,
+// without cutting a character in two.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for i in line.chars() {
+        if length + i.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(i);
+        length += i.len_utf8();
+    }
+    folded.push_str("\r\n");
+    return folded;
+}
+
+fn to_vtodo(task: &Task) -> Vec<String> {
+    let modified = task.modification().to_utc_timestamp();
+    let mut lines = vec![
+        String::from("BEGIN:VTODO"),
+        format!("UID:{}", task_uid(task)),
+        format!("DTSTAMP:{}", modified),
+        format!("LAST-MODIFIED:{}", modified),
+        format!("SUMMARY:{}", escape(&task.get_name())),
+    ];
+    if let Some(i) = task.description() {
+        lines.push(format!("DESCRIPTION:{}", escape(i)));
+    }
+    if let Some(i) = task.term() {
+        lines.push(format!(
+            "DUE;VALUE=DATE:{}",
+            i.to_iso_string().replace('-', "")
+        ));
+    }
+    if let Some(i) = task.task_state() {
+        lines.push(format!("STATUS:{}", status(i)));
+    }
+    if let Some(i) = task.priority() {
+        lines.push(format!("PRIORITY:{}", priority(i)));
+    }
+    if !task.tags().is_empty() {
+        let categories = task
+            .tags()
+            .iter()
+            .map(|i| escape(i))
+            .collect::<Vec<String>>();
+        lines.push(format!("CATEGORIES:{}", categories.join(",")));
+    }
+    lines.push(String::from("END:VTODO"));
+    return lines;
+}
+
+// Only the tasks with a term are written, the others have no place in a
+// calendar.
+pub fn write_tasks<W: Write>(out: &mut W, tasks: &[Task]) -> Result<(), TaskError> {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        format!(
+            "PRODID:-//todo_list//todo_list {}//EN",
+            env!("CARGO_PKG_VERSION")
+        ),
+        String::from("CALSCALE:GREGORIAN"),
+    ];
+    for i in tasks.iter().filter(|i| i.term().is_some()) {
+        lines.extend(to_vtodo(i));
+    }
+    lines.push(String::from("END:VCALENDAR"));
+    for i in lines {
+        out.write_all(fold(&i).as_bytes())?;
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests_ical {
+    use super::*;
+    use crate::{ConcreteTaskBuilder, Date, TaskBuilder};
+
+    #[test]
+    fn test_escape_and_fold() {
+        assert_eq!(escape("a,b;c\\d\r\ne"), String::from("a\\,b\\;c\\\\d\\ne"));
+        let line = format!("SUMMARY:{}", "é".repeat(40));
+        let folded = fold(&line);
+        let lines: Vec<&str> = folded.trim_end().split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|i| i.len() <= MAX_LINE_OCTETS));
+        assert_eq!(lines.concat().replacen(" ", "", 1), line);
+    }
+
+    #[test]
+    fn test_write_tasks_with_terms() {
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Renew TLS, api"))
+            .set_description(String::from("before\nMarch"))
+            .set_term(Date::from_iso_string("2026-03-01").unwrap())
+            .set_task_state(TaskState::InProgress)
+            .set_priority(Priority::Urgent)
+            .set_tags(vec![String::from("ops"), String::from("api")])
+            .get_task();
+        let no_term = ConcreteTaskBuilder::new().get_task();
+        let mut out = Vec::new();
+
+        write_tasks(&mut out, &[task.clone(), no_term]).unwrap();
+
+        let modified = task.modification().to_utc_timestamp();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//todo_list//todo_list {}//EN\r\nCALSCALE:GREGORIAN\r\nBEGIN:VTODO\r\nUID:task-{}@todo_list\r\nDTSTAMP:{}\r\nLAST-MODIFIED:{}\r\nSUMMARY:Renew TLS\\, api\r\nDESCRIPTION:before\\nMarch\r\nDUE;VALUE=DATE:20260301\r\nSTATUS:IN-PROCESS\r\nPRIORITY:1\r\nCATEGORIES:api,ops\r\nEND:VTODO\r\nEND:VCALENDAR\r\n",
+                env!("CARGO_PKG_VERSION"),
+                task.get_id(),
+                modified,
+                modified
+            )
+        );
+    }
+}
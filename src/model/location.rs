@@ -0,0 +1,128 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+pub const DATABASE_DIR_NAME: &str = ".todo_list";
+pub const DATABASE_FILE_NAME: &str = "info.db3";
+pub const GLOBAL_DIR_NAME: &str = "todo_list";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DatabaseSource {
+    Explicit,
+    Discovered,
+    Global,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseLocation {
+    pub path: PathBuf,
+    pub source: DatabaseSource,
+}
+
+pub fn local_database_path(dir: &Path) -> PathBuf {
+    return dir.join(DATABASE_DIR_NAME).join(DATABASE_FILE_NAME);
+}
+
+// Like git, the nearest .todo_list in the current directory or one of its
+// parents wins, so a task list can be kept per repository.
+pub fn discover(start: &Path) -> Option<PathBuf> {
+    return start
+        .ancestors()
+        .map(local_database_path)
+        .find(|i| i.is_file());
+}
+
+pub fn global_database_path() -> Option<PathBuf> {
+    return global_database_path_from(env::var_os("XDG_DATA_HOME"), env::var_os("HOME"));
+}
+
+fn global_database_path_from(
+    xdg_data_home: Option<OsString>,
+    home: Option<OsString>,
+) -> Option<PathBuf> {
+    // The XDG spec asks to ignore relative paths in its variables.
+    let data_home = match xdg_data_home.map(PathBuf::from) {
+        Some(i) if i.is_absolute() => i,
+        _ => PathBuf::from(home?).join(".local").join("share"),
+    };
+    return Some(data_home.join(GLOBAL_DIR_NAME).join(DATABASE_FILE_NAME));
+}
+
+pub fn resolve(explicit: Option<PathBuf>, start: &Path) -> Option<DatabaseLocation> {
+    if let Some(i) = explicit {
+        return Some(DatabaseLocation {
+            path: i,
+            source: DatabaseSource::Explicit,
+        });
+    }
+    if let Some(i) = discover(start) {
+        return Some(DatabaseLocation {
+            path: i,
+            source: DatabaseSource::Discovered,
+        });
+    }
+    return global_database_path().map(|i| DatabaseLocation {
+        path: i,
+        source: DatabaseSource::Global,
+    });
+}
+
+#[cfg(test)]
+mod tests_location {
+    use super::*;
+    use std::fs;
+
+    const DIR_TEST_PATH: &str = "./.todo_list_location_test";
+
+    #[test]
+    fn test_discover_in_parent() {
+        let root = PathBuf::from(DIR_TEST_PATH);
+        let nested = root.join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir(root.join(DATABASE_DIR_NAME)).unwrap();
+        fs::write(local_database_path(&root), "").unwrap();
+
+        let found = discover(&nested);
+        let missing = discover(Path::new("/"));
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(found, Some(local_database_path(&root)));
+        assert_eq!(missing, None, "The root should not have a task list");
+    }
+
+    #[test]
+    fn test_explicit_path_wins() {
+        let location = resolve(Some(PathBuf::from("/tmp/tasks.db3")), Path::new("/"));
+        assert_eq!(
+            location,
+            Some(DatabaseLocation {
+                path: PathBuf::from("/tmp/tasks.db3"),
+                source: DatabaseSource::Explicit,
+            })
+        );
+    }
+
+    #[test]
+    fn test_global_path() {
+        assert_eq!(
+            global_database_path_from(
+                Some(OsString::from("/data")),
+                Some(OsString::from("/home/a"))
+            ),
+            Some(PathBuf::from("/data/todo_list/info.db3"))
+        );
+        assert_eq!(
+            global_database_path_from(None, Some(OsString::from("/home/a"))),
+            Some(PathBuf::from("/home/a/.local/share/todo_list/info.db3"))
+        );
+        assert_eq!(
+            global_database_path_from(
+                Some(OsString::from("data")),
+                Some(OsString::from("/home/a"))
+            ),
+            Some(PathBuf::from("/home/a/.local/share/todo_list/info.db3")),
+            "A relative XDG_DATA_HOME should be ignored"
+        );
+        assert_eq!(global_database_path_from(None, None), None);
+    }
+}
@@ -0,0 +1,734 @@
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, Transaction, TransactionBehavior};
+use std::error::Error;
+
+use crate::model::error::TaskError;
+use crate::Date;
+use crate::Priority;
+use crate::TaskState;
+
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+// Append new steps to the end, never edit or reorder the ones already released:
+// the position of a step is the schema version it upgrades the database to.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create tasks table",
+        up: create_tasks_table,
+    },
+    Migration {
+        version: 2,
+        description: "store tasks with typed columns",
+        up: store_typed_columns,
+    },
+    Migration {
+        version: 3,
+        description: "add short task numbers",
+        up: add_task_numbers,
+    },
+    Migration {
+        version: 4,
+        description: "create settings table",
+        up: create_settings_table,
+    },
+    Migration {
+        version: 5,
+        description: "index names and descriptions for full-text search",
+        up: create_search_index,
+    },
+    Migration {
+        version: 6,
+        description: "add uuids shared with other tools",
+        up: add_task_uuids,
+    },
+    Migration {
+        version: 7,
+        description: "create tags tables",
+        up: create_tags_tables,
+    },
+    Migration {
+        version: 8,
+        description: "create projects table",
+        up: create_projects_table,
+    },
+    Migration {
+        version: 9,
+        description: "add parent tasks",
+        up: add_parent_tasks,
+    },
+    Migration {
+        version: 10,
+        description: "create task dependencies table",
+        up: create_task_dependencies_table,
+    },
+];
+
+fn create_tasks_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("CREATE TABLE IF NOT EXISTS tasks(id TEXT PRIMARY KEY, name TEXT, description TEXT, date TEXT, term TEXT, task_state TEXT, priority TEXT)")?;
+    return Ok(());
+}
+
+// Version 1 stored every column as TEXT, used the literal "None" for absent
+// values and wrote dates as d-m-Y. Rows are converted one by one so a value
+// that cannot be understood aborts the upgrade instead of being dropped.
+fn store_typed_columns(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE tasks_typed(
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT,
+            modification TEXT NOT NULL,
+            term TEXT,
+            task_state INTEGER CHECK (task_state BETWEEN 1 AND 3),
+            priority INTEGER CHECK (priority BETWEEN 1 AND 4)
+        )",
+    )?;
+
+    {
+        let mut select = tx
+            .prepare("SELECT id, name, description, date, term, task_state, priority FROM tasks")?;
+        let mut insert = tx.prepare(
+            "INSERT INTO tasks_typed(id, name, description, modification, term, task_state, priority)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        let mut rows = select.query([])?;
+        while let Some(row) = rows.next()? {
+            let id: String = row.get(0)?;
+            let id: u64 = id.parse().map_err(|err| legacy_error(0, Box::new(err)))?;
+            let name: Option<String> = row.get(1)?;
+            let description = legacy_optional(row.get(2)?);
+            let modification = match legacy_date(3, row.get(3)?)? {
+                Some(i) => i,
+                None => return Err(legacy_error(3, "missing modification date".into())),
+            };
+            let term = legacy_date(4, row.get(4)?)?;
+            let task_state = legacy_value(5, row.get(5)?, "state", TaskState::from_string)?;
+            let priority = legacy_value(6, row.get(6)?, "priority", Priority::from_string)?;
+
+            insert.execute(params![
+                id as i64,
+                name.unwrap_or_else(|| format!("TASK:{}", id)),
+                description,
+                modification,
+                term,
+                task_state,
+                priority
+            ])?;
+        }
+    }
+
+    tx.execute_batch("DROP TABLE tasks; ALTER TABLE tasks_typed RENAME TO tasks;")?;
+    return Ok(());
+}
+
+// Existing tasks are numbered by age, the counter keeps the last number given
+// so a deleted task does not hand its number to the next one.
+fn add_task_numbers(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE tasks ADD COLUMN number INTEGER;
+        UPDATE tasks SET number = (
+            SELECT COUNT(*) FROM tasks AS older
+                WHERE older.modification < tasks.modification
+                    OR (older.modification = tasks.modification AND older.rowid <= tasks.rowid)
+        );
+        CREATE UNIQUE INDEX tasks_number ON tasks(number);
+        CREATE TABLE counters(name TEXT PRIMARY KEY, value INTEGER NOT NULL);
+        INSERT INTO counters(name, value) VALUES ('task_number', (SELECT COUNT(*) FROM tasks));",
+    )?;
+    return Ok(());
+}
+
+fn create_settings_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("CREATE TABLE settings(name TEXT PRIMARY KEY, value TEXT NOT NULL)")?;
+    return Ok(());
+}
+
+// The index only stores the words, the text is read back from tasks through
+// the id, and the triggers keep both tables in step for every writer.
+fn create_search_index(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE VIRTUAL TABLE tasks_search USING fts5(
+            name,
+            description,
+            content = 'tasks',
+            content_rowid = 'id',
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+        CREATE TRIGGER tasks_search_insert AFTER INSERT ON tasks BEGIN
+            INSERT INTO tasks_search(rowid, name, description)
+                VALUES (new.id, new.name, new.description);
+        END;
+        CREATE TRIGGER tasks_search_delete AFTER DELETE ON tasks BEGIN
+            INSERT INTO tasks_search(tasks_search, rowid, name, description)
+                VALUES ('delete', old.id, old.name, old.description);
+        END;
+        CREATE TRIGGER tasks_search_update AFTER UPDATE OF id, name, description ON tasks BEGIN
+            INSERT INTO tasks_search(tasks_search, rowid, name, description)
+                VALUES ('delete', old.id, old.name, old.description);
+            INSERT INTO tasks_search(rowid, name, description)
+                VALUES (new.id, new.name, new.description);
+        END;
+        INSERT INTO tasks_search(tasks_search) VALUES ('rebuild');",
+    )?;
+    return Ok(());
+}
+
+fn add_task_uuids(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE tasks ADD COLUMN uuid TEXT;
+        CREATE UNIQUE INDEX tasks_uuid ON tasks(uuid);",
+    )?;
+    return Ok(());
+}
+
+// The links go with their task, and a tag left without tasks is removed so
+// the list of tags only shows the ones in use.
+fn create_tags_tables(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE tags(id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+        CREATE TABLE task_tags(
+            task_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (task_id, tag_id)
+        );
+        CREATE INDEX task_tags_tag ON task_tags(tag_id);
+        CREATE TRIGGER tasks_tags_delete AFTER DELETE ON tasks BEGIN
+            DELETE FROM task_tags WHERE task_id = old.id;
+        END;
+        CREATE TRIGGER task_tags_unused AFTER DELETE ON task_tags BEGIN
+            DELETE FROM tags WHERE id = old.tag_id
+                AND NOT EXISTS (SELECT 1 FROM task_tags WHERE tag_id = old.tag_id);
+        END;",
+    )?;
+    return Ok(());
+}
+
+// Names are compared without case so "API" and "api" cannot be two projects.
+// The tasks of a deleted project are left without one, as SQLite does not
+// enforce the foreign key unless asked on every connection.
+fn create_projects_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE projects(
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            description TEXT,
+            priority INTEGER CHECK (priority BETWEEN 1 AND 4),
+            archived INTEGER NOT NULL DEFAULT 0
+        );
+        ALTER TABLE tasks ADD COLUMN project_id INTEGER REFERENCES projects(id);
+        CREATE INDEX tasks_project ON tasks(project_id);
+        CREATE TRIGGER projects_delete AFTER DELETE ON projects BEGIN
+            UPDATE tasks SET project_id = NULL WHERE project_id = old.id;
+        END;",
+    )?;
+    return Ok(());
+}
+
+// The subtasks of a deleted task become top-level tasks unless the subtask
+// policy deleted them first, so no task points at a missing parent.
+fn add_parent_tasks(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE tasks ADD COLUMN parent_id INTEGER REFERENCES tasks(id);
+        CREATE INDEX tasks_parent ON tasks(parent_id);
+        CREATE TRIGGER tasks_parent_delete AFTER DELETE ON tasks BEGIN
+            UPDATE tasks SET parent_id = NULL WHERE parent_id = old.id;
+        END;",
+    )?;
+    return Ok(());
+}
+
+// A row means the blocker must end before the blocked task starts. The rows
+// of a deleted task go with it, so what it blocked is free to start.
+fn create_task_dependencies_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE task_dependencies(
+            blocker_id INTEGER NOT NULL REFERENCES tasks(id),
+            blocked_id INTEGER NOT NULL REFERENCES tasks(id),
+            PRIMARY KEY (blocker_id, blocked_id),
+            CHECK (blocker_id != blocked_id)
+        );
+        CREATE INDEX task_dependencies_blocked ON task_dependencies(blocked_id);
+        CREATE TRIGGER tasks_dependencies_delete AFTER DELETE ON tasks BEGIN
+            DELETE FROM task_dependencies WHERE blocker_id = old.id OR blocked_id = old.id;
+        END;",
+    )?;
+    return Ok(());
+}
+
+fn legacy_optional(value: Option<String>) -> Option<String> {
+    return value.filter(|i| i != "None");
+}
+
+fn legacy_date(column: usize, value: Option<String>) -> rusqlite::Result<Option<Date>> {
+    return match legacy_optional(value) {
+        Some(i) => match Date::from_string(i.clone()) {
+            Ok(j) => Ok(j),
+            Err(_) => Err(legacy_error(column, format!("invalid date {:?}", i).into())),
+        },
+        None => Ok(None),
+    };
+}
+
+// Older versions wrote states and priorities with other casings, anything
+// that is still unknown aborts the upgrade like an unreadable date.
+fn legacy_value<T>(
+    column: usize,
+    value: Option<String>,
+    kind: &str,
+    parse: fn(&str) -> Option<T>,
+) -> rusqlite::Result<Option<T>> {
+    return match legacy_optional(value) {
+        Some(i) => match parse(&i.trim().to_lowercase()) {
+            Some(j) => Ok(Some(j)),
+            None => Err(legacy_error(
+                column,
+                format!("invalid {} {:?}", kind, i).into(),
+            )),
+        },
+        None => Ok(None),
+    };
+}
+
+fn legacy_error(column: usize, err: Box<dyn Error + Send + Sync>) -> rusqlite::Error {
+    return rusqlite::Error::FromSqlConversionFailure(column, Type::Text, err);
+}
+
+fn latest_version_of(migrations: &[Migration]) -> u32 {
+    return migrations.last().map(|i| i.version).unwrap_or(0);
+}
+
+pub fn schema_version(conn: &Connection) -> rusqlite::Result<u32> {
+    return conn.query_row("PRAGMA user_version", [], |row| row.get(0));
+}
+
+pub fn migrate(conn: &Connection) -> Result<u32, TaskError> {
+    return migrate_with(conn, MIGRATIONS);
+}
+
+fn migrate_with(conn: &Connection, migrations: &[Migration]) -> Result<u32, TaskError> {
+    let latest = latest_version_of(migrations);
+    let mut version = check_version(schema_version(conn)?, latest)?;
+    if version == latest {
+        return Ok(version);
+    }
+
+    // The immediate transaction takes the write lock before the version is read
+    // again, so two processes opening an old database do not both upgrade it.
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+    let current = check_version(schema_version(&tx)?, latest)?;
+    for migration in migrations.iter().filter(|i| i.version > current) {
+        (migration.up)(&tx).map_err(|err| TaskError::Migration {
+            version: migration.version,
+            description: migration.description,
+            source: err,
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        version = migration.version;
+    }
+    tx.commit()?;
+
+    return Ok(version);
+}
+
+fn check_version(version: u32, latest: u32) -> Result<u32, TaskError> {
+    if version > latest {
+        return Err(TaskError::SchemaMismatch {
+            found: version,
+            supported: latest,
+        });
+    }
+    return Ok(version);
+}
+
+#[cfg(test)]
+mod tests_migration {
+    use super::*;
+
+    fn latest_version() -> u32 {
+        return latest_version_of(MIGRATIONS);
+    }
+
+    fn count_tasks(conn: &Connection) -> i64 {
+        return conn
+            .query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))
+            .unwrap();
+    }
+
+    fn failing_step(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute_batch("CREATE TABLE half_done(id INTEGER)")?;
+        tx.execute_batch("THIS IS NOT SQL")?;
+        return Ok(());
+    }
+
+    #[test]
+    fn test_versions_are_sequential() {
+        for (i, j) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(
+                j.version as usize,
+                i + 1,
+                "Versions should start at one and increase by one"
+            );
+        }
+    }
+
+    #[test]
+    fn test_migrate_new_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        let version = migrate(&conn).expect("A new database should be migrated");
+
+        assert_eq!(version, latest_version());
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        assert_eq!(
+            count_tasks(&conn),
+            0,
+            "The tasks table should exist and be empty"
+        );
+    }
+
+    #[test]
+    fn test_migrate_is_idempotent() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        let version = migrate(&conn).expect("Migrating twice should be okay");
+        assert_eq!(version, latest_version());
+    }
+
+    #[test]
+    fn test_migrate_unversioned_database_keeps_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE tasks(id TEXT PRIMARY KEY, name TEXT, description TEXT, date TEXT, term TEXT, task_state TEXT, priority TEXT)").unwrap();
+        conn.execute(
+            "INSERT INTO tasks VALUES ('1', 'Old', 'None', '1-1-2024', 'None', 'pending', 'low')",
+            [],
+        )
+        .unwrap();
+
+        migrate(&conn).expect("A database created before versioning should be migrated");
+
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        assert_eq!(count_tasks(&conn), 1, "Existing tasks should be kept");
+    }
+
+    #[test]
+    fn test_convert_legacy_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE tasks(id TEXT PRIMARY KEY, name TEXT, description TEXT, date TEXT, term TEXT, task_state TEXT, priority TEXT)").unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks VALUES ('18446744073709551615', 'Full', 'Some text', '5-3-2024', '12-1-2025', 'in progress', 'urgent');
+            INSERT INTO tasks VALUES ('7', 'Empty', 'None', '1-1-2024', 'None', 'None', 'None');",
+        )
+        .unwrap();
+
+        migrate(&conn).expect("Legacy rows should be converted");
+
+        let full = conn
+            .query_row(
+                "SELECT id, description, modification, term, task_state, priority FROM tasks WHERE name = 'Full'",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                        row.get::<_, Option<i64>>(4)?,
+                        row.get::<_, Option<i64>>(5)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(full.0 as u64, u64::MAX, "Ids above i64::MAX should be kept");
+        assert_eq!(full.1, Some(String::from("Some text")));
+        assert_eq!(full.2, String::from("2024-03-05"));
+        assert_eq!(full.3, Some(String::from("2025-01-12")));
+        assert_eq!(full.4, Some(2));
+        assert_eq!(full.5, Some(4));
+
+        let empty = conn
+            .query_row(
+                "SELECT description, term, task_state, priority FROM tasks WHERE id = 7",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, Option<String>>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, Option<i64>>(2)?,
+                        row.get::<_, Option<i64>>(3)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            empty,
+            (None, None, None, None),
+            "\"None\" should become NULL"
+        );
+    }
+
+    #[test]
+    fn test_invalid_legacy_row_aborts_upgrade() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE tasks(id TEXT PRIMARY KEY, name TEXT, description TEXT, date TEXT, term TEXT, task_state TEXT, priority TEXT)").unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks VALUES ('1', 'Bad', 'None', '1-1-2024', '45-1-2025', 'None', 'None');",
+        )
+        .unwrap();
+
+        assert!(
+            migrate(&conn).is_err(),
+            "An unreadable term should be reported"
+        );
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+        let term: String = conn
+            .query_row("SELECT term FROM tasks WHERE id = '1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(
+            term,
+            String::from("45-1-2025"),
+            "The old row should be untouched"
+        );
+    }
+
+    #[test]
+    fn test_legacy_state_and_priority() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE tasks(id TEXT PRIMARY KEY, name TEXT, description TEXT, date TEXT, term TEXT, task_state TEXT, priority TEXT)").unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks VALUES ('1', 'Cased', 'None', '1-1-2024', 'None', 'Ended', 'HIGH');",
+        )
+        .unwrap();
+
+        migrate(&conn).expect("Other casings should be understood");
+        let cased: (Option<i64>, Option<i64>) = conn
+            .query_row("SELECT task_state, priority FROM tasks", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(cased, (Some(3), Some(3)));
+
+        for (state, priority) in [("'done'", "'None'"), ("'None'", "'H'")] {
+            let conn = Connection::open_in_memory().unwrap();
+            conn.execute_batch("CREATE TABLE tasks(id TEXT PRIMARY KEY, name TEXT, description TEXT, date TEXT, term TEXT, task_state TEXT, priority TEXT)").unwrap();
+            conn.execute_batch(&format!(
+                "INSERT INTO tasks VALUES ('1', 'Bad', 'None', '1-1-2024', 'None', {}, {});",
+                state, priority
+            ))
+            .unwrap();
+
+            assert!(
+                migrate(&conn).is_err(),
+                "An unknown state or priority should be reported, not dropped"
+            );
+            assert_eq!(schema_version(&conn).unwrap(), 0);
+        }
+    }
+
+    #[test]
+    fn test_number_existing_tasks_by_age() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE tasks(id TEXT PRIMARY KEY, name TEXT, description TEXT, date TEXT, term TEXT, task_state TEXT, priority TEXT)").unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks VALUES ('30', 'Newest', 'None', '3-1-2024', 'None', 'None', 'None');
+            INSERT INTO tasks VALUES ('10', 'Oldest', 'None', '1-1-2024', 'None', 'None', 'None');
+            INSERT INTO tasks VALUES ('20', 'Middle', 'None', '2-1-2024', 'None', 'None', 'None');",
+        )
+        .unwrap();
+
+        migrate(&conn).unwrap();
+
+        let mut select = conn
+            .prepare("SELECT name FROM tasks ORDER BY number")
+            .unwrap();
+        let names = select
+            .query_map([], |row| row.get::<_, String>(0))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .unwrap();
+        assert_eq!(names, vec!["Oldest", "Middle", "Newest"]);
+        let counter: i64 = conn
+            .query_row(
+                "SELECT value FROM counters WHERE name = 'task_number'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(counter, 3, "The next task should get number 4");
+    }
+
+    #[test]
+    fn test_index_existing_tasks() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE tasks(id TEXT PRIMARY KEY, name TEXT, description TEXT, date TEXT, term TEXT, task_state TEXT, priority TEXT)").unwrap();
+        conn.execute(
+            "INSERT INTO tasks VALUES ('1', 'Old', 'Renew the TLS certificate', '1-1-2024', 'None', 'None', 'None')",
+            [],
+        )
+        .unwrap();
+
+        migrate(&conn).unwrap();
+
+        let found: i64 = conn
+            .query_row(
+                "SELECT rowid FROM tasks_search WHERE tasks_search MATCH 'certificate'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(found, 1, "Tasks created before the index should be found");
+    }
+
+    #[test]
+    fn test_tags_follow_their_tasks() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks(id, name, modification, number) VALUES (1, 'A', '2026-01-01', 1), (2, 'B', '2026-01-01', 2);
+            INSERT INTO tags(id, name) VALUES (1, 'ui'), (2, 'docs');
+            INSERT INTO task_tags VALUES (1, 1), (2, 1), (1, 2);",
+        )
+        .unwrap();
+
+        conn.execute("DELETE FROM tasks WHERE id = 1", []).unwrap();
+
+        let tags: Vec<String> = conn
+            .prepare("SELECT name FROM tags")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .unwrap();
+        assert_eq!(tags, vec!["ui"], "docs had no other task");
+        let links: i64 = conn
+            .query_row("SELECT COUNT(*) FROM task_tags", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(links, 1);
+    }
+
+    #[test]
+    fn test_deleted_project_leaves_its_tasks() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO projects(id, name) VALUES (1, 'Backend');
+            INSERT INTO tasks(id, name, modification, number, project_id) VALUES (1, 'A', '2026-01-01', 1, 1);",
+        )
+        .unwrap();
+
+        assert!(
+            conn.execute("INSERT INTO projects(name) VALUES ('backend')", [])
+                .is_err(),
+            "Names should be unique without case"
+        );
+        conn.execute("DELETE FROM projects WHERE id = 1", [])
+            .unwrap();
+
+        let project: Option<i64> = conn
+            .query_row("SELECT project_id FROM tasks WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(project, None);
+    }
+
+    #[test]
+    fn test_deleted_parent_leaves_its_subtasks() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks(id, name, modification, number) VALUES (1, 'Parent', '2026-01-01', 1);
+            INSERT INTO tasks(id, name, modification, number, parent_id) VALUES (2, 'Child', '2026-01-01', 2, 1);",
+        )
+        .unwrap();
+
+        conn.execute("DELETE FROM tasks WHERE id = 1", []).unwrap();
+
+        let parent: Option<i64> = conn
+            .query_row("SELECT parent_id FROM tasks WHERE id = 2", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(parent, None);
+    }
+
+    #[test]
+    fn test_deleted_task_leaves_no_dependencies() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks(id, name, modification, number) VALUES (1, 'Build', '2026-01-01', 1);
+            INSERT INTO tasks(id, name, modification, number) VALUES (2, 'Deploy', '2026-01-01', 2);
+            INSERT INTO task_dependencies(blocker_id, blocked_id) VALUES (1, 2);",
+        )
+        .unwrap();
+
+        conn.execute("DELETE FROM tasks WHERE id = 1", []).unwrap();
+
+        let dependencies: i64 = conn
+            .query_row("SELECT COUNT(*) FROM task_dependencies", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(dependencies, 0);
+    }
+
+    #[test]
+    fn test_refuse_newer_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+
+        let result = migrate(&conn);
+
+        assert!(
+            matches!(result, Err(TaskError::SchemaMismatch { .. })),
+            "A newer database should not be opened"
+        );
+        assert_eq!(schema_version(&conn).unwrap(), latest_version() + 1);
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let conn = Connection::open_in_memory().unwrap();
+        let migrations = [
+            Migration {
+                version: 1,
+                description: "create tasks table",
+                up: create_tasks_table,
+            },
+            Migration {
+                version: 2,
+                description: "broken step",
+                up: failing_step,
+            },
+        ];
+
+        let result = migrate_with(&conn, &migrations);
+
+        assert!(
+            matches!(result, Err(TaskError::Migration { version: 2, .. })),
+            "The broken step should be reported"
+        );
+        assert_eq!(
+            schema_version(&conn).unwrap(),
+            0,
+            "No step should be recorded when the upgrade fails"
+        );
+        let tables: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 0, "The partial upgrade should be rolled back");
+    }
+}
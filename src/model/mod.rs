@@ -1,4 +1,20 @@
+pub mod critical_path;
 pub mod date;
+pub mod delimited;
+pub mod error;
+pub mod filter;
+pub mod ical;
+pub mod location;
+pub mod migration;
+pub mod name_policy;
 pub mod priority;
+pub mod project;
+pub mod query;
+pub mod report_column;
+pub mod search;
+pub mod subtask_policy;
+pub mod tag;
 pub mod task;
 pub mod task_state;
+pub mod taskwarrior;
+pub mod todotxt;
@@ -0,0 +1,49 @@
+use std::fmt::Display;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum NamePolicy {
+    #[default]
+    Duplicates,
+    Unique,
+}
+
+impl NamePolicy {
+    pub fn to_str(&self) -> &str {
+        return match self {
+            Self::Duplicates => "duplicates",
+            Self::Unique => "unique",
+        };
+    }
+
+    pub fn from_string(policy: &str) -> Option<NamePolicy> {
+        return match policy {
+            "duplicates" => Some(Self::Duplicates),
+            "unique" => Some(Self::Unique),
+            _ => None,
+        };
+    }
+}
+
+impl Display for NamePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+#[cfg(test)]
+mod tests_name_policy {
+    use super::*;
+
+    #[test]
+    fn test_from_string() {
+        for i in [NamePolicy::Duplicates, NamePolicy::Unique] {
+            assert_eq!(NamePolicy::from_string(i.to_str()), Some(i));
+        }
+        assert_eq!(NamePolicy::from_string("Unique"), None);
+        assert_eq!(
+            NamePolicy::default(),
+            NamePolicy::Duplicates,
+            "Old databases allow repeated names"
+        );
+    }
+}
@@ -1,3 +1,7 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Serialize, Serializer};
+use std::fmt::Display;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Priority {
     Low,
@@ -16,12 +20,8 @@ impl Priority {
         };
     }
 
-    pub fn to_string(&self) -> String {
-        return String::from(self.to_str());
-    }
-
-    pub fn from_string(priority: &String) -> Option<Priority> {
-        return match priority.as_str() {
+    pub fn from_string(priority: &str) -> Option<Priority> {
+        return match priority {
             "low" => Some(Self::Low),
             "normal" => Some(Self::Normal),
             "high" => Some(Self::High),
@@ -30,7 +30,28 @@ impl Priority {
         };
     }
 
-    fn to_numeric(&self) -> usize {
+    // The letters of todo.txt and of the Org priority cookies, A is the most
+    // important.
+    pub fn to_letter(&self) -> char {
+        return match self {
+            Self::Urgent => 'A',
+            Self::High => 'B',
+            Self::Normal => 'C',
+            Self::Low => 'D',
+        };
+    }
+
+    pub fn from_letter(letter: char) -> Option<Priority> {
+        return match letter {
+            'A' => Some(Self::Urgent),
+            'B' => Some(Self::High),
+            'C' => Some(Self::Normal),
+            'D' => Some(Self::Low),
+            _ => None,
+        };
+    }
+
+    pub fn to_numeric(&self) -> usize {
         return match self {
             Self::Low => 1,
             Self::Normal => 2,
@@ -38,6 +59,44 @@ impl Priority {
             Self::Urgent => 4,
         };
     }
+
+    pub fn from_numeric(priority: usize) -> Option<Priority> {
+        return match priority {
+            1 => Some(Self::Low),
+            2 => Some(Self::Normal),
+            3 => Some(Self::High),
+            4 => Some(Self::Urgent),
+            _ => None,
+        };
+    }
+}
+
+impl Serialize for Priority {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_str(self.to_str());
+    }
+}
+
+impl ToSql for Priority {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        return Ok(ToSqlOutput::from(self.to_numeric() as i64));
+    }
+}
+
+impl FromSql for Priority {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let numeric = value.as_i64()?;
+        return usize::try_from(numeric)
+            .ok()
+            .and_then(Priority::from_numeric)
+            .ok_or(FromSqlError::OutOfRange(numeric));
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
 }
 
 impl PartialOrd for Priority {
@@ -69,8 +128,24 @@ impl PartialOrd for Priority {
 }
 
 #[cfg(test)]
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
 mod tests_priority {
     use super::*;
+
+    #[test]
+    fn test_letters() {
+        for i in [
+            Priority::Low,
+            Priority::Normal,
+            Priority::High,
+            Priority::Urgent,
+        ] {
+            assert_eq!(Priority::from_letter(i.to_letter()), Some(i));
+        }
+        assert_eq!(Priority::Urgent.to_letter(), 'A');
+        assert_eq!(Priority::from_letter('E'), None);
+    }
+
     #[test]
     fn test_to_str() {
         assert_eq!(
@@ -119,6 +194,20 @@ mod tests_priority {
         );
     }
 
+    #[test]
+    fn from_numeric() {
+        for i in [
+            Priority::Low,
+            Priority::Normal,
+            Priority::High,
+            Priority::Urgent,
+        ] {
+            assert_eq!(Priority::from_numeric(i.to_numeric()), Some(i));
+        }
+        assert_eq!(Priority::from_numeric(0), None, "0 is not a priority");
+        assert_eq!(Priority::from_numeric(5), None, "5 is not a priority");
+    }
+
     #[test]
     fn to_numeric_low() {
         let a = Priority::Low.to_numeric();
@@ -0,0 +1,81 @@
+use serde::Serialize;
+
+use crate::model::error::TaskError;
+use crate::Priority;
+
+// The priority is given to the tasks created in the project without one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Project {
+    pub name: String,
+    pub description: Option<String>,
+    pub priority: Option<Priority>,
+    pub archived: bool,
+}
+
+impl Project {
+    pub fn new(name: String) -> Self {
+        return Project {
+            name,
+            description: None,
+            priority: None,
+            archived: false,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectSummary {
+    #[serde(flatten)]
+    pub project: Project,
+    pub tasks: u64,
+    pub ended: u64,
+}
+
+impl ProjectSummary {
+    pub fn progress(&self) -> String {
+        return progress(self.ended, self.tasks);
+    }
+}
+
+// The percentage is rounded down so a project only shows 100% when every
+// task has ended.
+pub fn progress(ended: u64, tasks: u64) -> String {
+    if tasks == 0 {
+        return String::from("no tasks");
+    }
+    return format!("{}/{} ended ({}%)", ended, tasks, ended * 100 / tasks);
+}
+
+// "none" stands for the tasks without a project in the filters, so no
+// project can take it.
+pub fn parse_project_name(name: &str) -> Result<String, TaskError> {
+    let name = name.trim();
+    if name.is_empty() || name.eq_ignore_ascii_case("none") || name.chars().any(char::is_control) {
+        return Err(TaskError::InvalidProject(String::from(name)));
+    }
+    return Ok(String::from(name));
+}
+
+#[cfg(test)]
+mod tests_project {
+    use super::*;
+
+    #[test]
+    fn test_parse_project_name() {
+        assert_eq!(parse_project_name(" Mobile app ").unwrap(), "Mobile app");
+        for i in ["", "  ", "None", "two\nlines"] {
+            assert!(
+                matches!(parse_project_name(i), Err(TaskError::InvalidProject(_))),
+                "{:?} should be refused",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn test_progress() {
+        assert_eq!(progress(0, 0), "no tasks");
+        assert_eq!(progress(2, 3), "2/3 ended (66%)");
+        assert_eq!(progress(4, 4), "4/4 ended (100%)");
+    }
+}
@@ -0,0 +1,469 @@
+use rusqlite::types::Value;
+
+use crate::Date;
+use crate::Priority;
+use crate::TaskState;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Number,
+    Id,
+    Name,
+    Description,
+    Modification,
+    Term,
+    TaskState,
+    Priority,
+}
+
+impl Column {
+    pub fn to_str(&self) -> &str {
+        return match self {
+            Self::Number => "number",
+            Self::Id => "id",
+            Self::Name => "name",
+            Self::Description => "description",
+            Self::Modification => "modification",
+            Self::Term => "term",
+            Self::TaskState => "task_state",
+            Self::Priority => "priority",
+        };
+    }
+
+    pub fn from_string(column: &str) -> Option<Column> {
+        return match column {
+            "number" | "#" => Some(Self::Number),
+            "id" => Some(Self::Id),
+            "name" => Some(Self::Name),
+            "description" => Some(Self::Description),
+            "modification" => Some(Self::Modification),
+            "term" => Some(Self::Term),
+            "task_state" | "state" => Some(Self::TaskState),
+            "priority" => Some(Self::Priority),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl Comparison {
+    // "!=" uses IS NOT so tasks without the field are kept, as "not high"
+    // includes tasks with no priority at all.
+    fn to_sql(&self) -> &str {
+        return match self {
+            Self::Equal => "=",
+            Self::NotEqual => "IS NOT",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
+        };
+    }
+
+    pub fn split_prefix(text: &str) -> (Comparison, &str) {
+        for (prefix, comparison) in [
+            (">=", Self::GreaterEqual),
+            ("<=", Self::LessEqual),
+            ("!=", Self::NotEqual),
+            (">", Self::Greater),
+            ("<", Self::Less),
+            ("=", Self::Equal),
+        ] {
+            if let Some(i) = text.strip_prefix(prefix) {
+                return (comparison, i.trim_start());
+            }
+        }
+        return (Self::Equal, text);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare {
+        column: Column,
+        comparison: Comparison,
+        value: Value,
+    },
+    IsNull {
+        column: Column,
+        negated: bool,
+    },
+    Contains {
+        column: Column,
+        text: String,
+    },
+    Search(String),
+    // A task with the tag, or with any tag when it is None.
+    HasTag(Option<String>),
+    // A task of the project, or of any project when it is None.
+    InProject(Option<String>),
+    // A task with a blocker that has not ended.
+    Blocked,
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    // Priorities and states are stored as the codes given by to_numeric and
+    // dates as ISO text, so SQL orders them like their PartialOrd.
+    pub fn priority(comparison: Comparison, priority: &Priority) -> Condition {
+        return Condition::Compare {
+            column: Column::Priority,
+            comparison,
+            value: Value::Integer(priority.to_numeric() as i64),
+        };
+    }
+
+    pub fn task_state(comparison: Comparison, task_state: &TaskState) -> Condition {
+        return Condition::Compare {
+            column: Column::TaskState,
+            comparison,
+            value: Value::Integer(task_state.to_numeric() as i64),
+        };
+    }
+
+    pub fn term(comparison: Comparison, date: &Date) -> Condition {
+        return Self::date(Column::Term, comparison, date);
+    }
+
+    pub fn date(column: Column, comparison: Comparison, date: &Date) -> Condition {
+        return Condition::Compare {
+            column,
+            comparison,
+            value: Value::Text(date.to_iso_string()),
+        };
+    }
+
+    fn to_sql(&self, values: &mut Vec<Value>) -> String {
+        return match self {
+            Self::Compare {
+                column,
+                comparison,
+                value,
+            } => {
+                values.push(value.clone());
+                format!("{} {} ?", column.to_str(), comparison.to_sql())
+            }
+            Self::IsNull { column, negated } => {
+                if *negated {
+                    format!("{} IS NOT NULL", column.to_str())
+                } else {
+                    format!("{} IS NULL", column.to_str())
+                }
+            }
+            Self::Contains { column, text } => {
+                values.push(Value::Text(format!("%{}%", escape_like(text))));
+                format!("{} LIKE ? ESCAPE '\\'", column.to_str())
+            }
+            Self::Search(text) => {
+                let pattern = format!("%{}%", escape_like(text));
+                values.push(Value::Text(pattern.clone()));
+                values.push(Value::Text(pattern));
+                String::from(
+                    "(name LIKE ? ESCAPE '\\' OR COALESCE(description, '') LIKE ? ESCAPE '\\')",
+                )
+            }
+            Self::HasTag(Some(tag)) => {
+                values.push(Value::Text(tag.clone()));
+                String::from(
+                    "EXISTS (SELECT 1 FROM task_tags JOIN tags ON tags.id = task_tags.tag_id
+                        WHERE task_tags.task_id = tasks.id AND tags.name = ?)",
+                )
+            }
+            Self::HasTag(None) => {
+                String::from("EXISTS (SELECT 1 FROM task_tags WHERE task_tags.task_id = tasks.id)")
+            }
+            Self::InProject(Some(project)) => {
+                values.push(Value::Text(project.clone()));
+                String::from("project_id IN (SELECT id FROM projects WHERE name = ?)")
+            }
+            Self::InProject(None) => String::from("project_id IS NOT NULL"),
+            Self::Blocked => format!("EXISTS (SELECT 1 {})", open_blockers()),
+            Self::And(conditions) => join_sql(conditions, " AND ", values),
+            Self::Or(conditions) => join_sql(conditions, " OR ", values),
+            // A comparison with NULL is NULL, and NOT NULL would drop the task
+            // again, so an unknown result counts as false before negating.
+            Self::Not(condition) => {
+                format!("NOT COALESCE({}, 0)", condition.to_sql(values))
+            }
+        };
+    }
+}
+
+// The blockers that keep a task waiting: the ones not ended yet, and none
+// for a task that has ended itself.
+pub fn open_blockers() -> String {
+    let ended = TaskState::Ended.to_numeric();
+    return format!(
+        "FROM task_dependencies JOIN tasks AS blockers ON blockers.id = task_dependencies.blocker_id
+            WHERE task_dependencies.blocked_id = tasks.id
+                AND COALESCE(blockers.task_state, 0) != {}
+                AND COALESCE(tasks.task_state, 0) != {}",
+        ended, ended
+    );
+}
+
+fn join_sql(conditions: &[Condition], separator: &str, values: &mut Vec<Value>) -> String {
+    let conditions = conditions
+        .iter()
+        .map(|i| i.to_sql(values))
+        .collect::<Vec<String>>();
+    return format!("({})", conditions.join(separator));
+}
+
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::new();
+    for i in text.chars() {
+        if matches!(i, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(i);
+    }
+    return escaped;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub column: Column,
+    pub descending: bool,
+}
+
+impl SortKey {
+    pub fn from_string(sort: &str) -> Option<SortKey> {
+        let (column, order) = match sort.split_once(':') {
+            Some((i, j)) => (i, j),
+            None => (sort, "asc"),
+        };
+        let descending = match order {
+            "asc" => false,
+            "desc" => true,
+            _ => return None,
+        };
+        return Some(SortKey {
+            column: Column::from_string(column)?,
+            descending,
+        });
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskQuery {
+    conditions: Vec<Condition>,
+    sort: Vec<SortKey>,
+    limit: Option<u64>,
+}
+
+impl TaskQuery {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        return self;
+    }
+
+    pub fn sort_by(mut self, sort: SortKey) -> Self {
+        self.sort.push(sort);
+        return self;
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        return self;
+    }
+
+    pub fn where_sql(&self, values: &mut Vec<Value>) -> String {
+        if self.conditions.is_empty() {
+            return String::new();
+        }
+        let conditions = self
+            .conditions
+            .iter()
+            .map(|i| i.to_sql(values))
+            .collect::<Vec<String>>();
+        return format!(" WHERE {}", conditions.join(" AND "));
+    }
+
+    // Returns what follows "FROM tasks" with a placeholder for each value,
+    // user text never becomes part of the statement itself.
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut values = Vec::new();
+        let mut sql = self.where_sql(&mut values);
+
+        let mut order = self
+            .sort
+            .iter()
+            .map(|i| {
+                let direction = if i.descending { "DESC" } else { "ASC" };
+                format!("{} {} NULLS LAST", i.column.to_str(), direction)
+            })
+            .collect::<Vec<String>>();
+        order.push(String::from("number ASC"));
+        sql += format!(" ORDER BY {}", order.join(", ")).as_str();
+
+        if let Some(i) = self.limit {
+            values.push(Value::Integer(i.min(i64::MAX as u64) as i64));
+            sql += " LIMIT ?";
+        }
+        return (sql, values);
+    }
+}
+
+#[cfg(test)]
+mod tests_query {
+    use super::*;
+
+    #[test]
+    fn test_split_prefix() {
+        assert_eq!(
+            Comparison::split_prefix(">=high"),
+            (Comparison::GreaterEqual, "high")
+        );
+        assert_eq!(Comparison::split_prefix("< low"), (Comparison::Less, "low"));
+        assert_eq!(
+            Comparison::split_prefix("!=ended"),
+            (Comparison::NotEqual, "ended")
+        );
+        assert_eq!(
+            Comparison::split_prefix("pending"),
+            (Comparison::Equal, "pending")
+        );
+    }
+
+    #[test]
+    fn test_sort_key_from_string() {
+        assert_eq!(
+            SortKey::from_string("term:desc"),
+            Some(SortKey {
+                column: Column::Term,
+                descending: true,
+            })
+        );
+        assert_eq!(
+            SortKey::from_string("priority"),
+            Some(SortKey {
+                column: Column::Priority,
+                descending: false,
+            })
+        );
+        assert_eq!(SortKey::from_string("term:up"), None);
+        assert_eq!(SortKey::from_string("color"), None);
+    }
+
+    #[test]
+    fn test_to_sql_uses_placeholders() {
+        let query = TaskQuery::new()
+            .filter(Condition::priority(
+                Comparison::GreaterEqual,
+                &Priority::High,
+            ))
+            .filter(Condition::Search(String::from("50%'; DROP")))
+            .sort_by(SortKey {
+                column: Column::Term,
+                descending: true,
+            })
+            .limit(3);
+
+        let (sql, values) = query.to_sql();
+
+        assert_eq!(
+            sql,
+            " WHERE priority >= ? AND (name LIKE ? ESCAPE '\\' OR COALESCE(description, '') LIKE ? ESCAPE '\\') ORDER BY term DESC NULLS LAST, number ASC LIMIT ?"
+        );
+        assert_eq!(
+            values,
+            vec![
+                Value::Integer(3),
+                Value::Text(String::from("%50\\%'; DROP%")),
+                Value::Text(String::from("%50\\%'; DROP%")),
+                Value::Integer(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_conditions() {
+        let query = TaskQuery::new().filter(Condition::Or(vec![
+            Condition::And(vec![
+                Condition::task_state(Comparison::Equal, &TaskState::Pending),
+                Condition::Not(Box::new(Condition::IsNull {
+                    column: Column::Term,
+                    negated: false,
+                })),
+            ]),
+            Condition::Contains {
+                column: Column::Name,
+                text: String::from("deploy"),
+            },
+        ]));
+
+        let (sql, values) = query.to_sql();
+
+        assert_eq!(
+            sql,
+            " WHERE ((task_state = ? AND NOT COALESCE(term IS NULL, 0)) OR name LIKE ? ESCAPE '\\') ORDER BY number ASC"
+        );
+        assert_eq!(
+            values,
+            vec![Value::Integer(1), Value::Text(String::from("%deploy%"))]
+        );
+    }
+
+    #[test]
+    fn test_tag_conditions() {
+        let query = TaskQuery::new()
+            .filter(Condition::HasTag(Some(String::from("ui"))))
+            .filter(Condition::Not(Box::new(Condition::HasTag(None))));
+
+        let (sql, values) = query.to_sql();
+
+        assert!(sql.contains("tags.name = ?)"));
+        assert!(sql.contains("AND NOT COALESCE(EXISTS (SELECT 1 FROM task_tags WHERE"));
+        assert_eq!(values, vec![Value::Text(String::from("ui"))]);
+    }
+
+    #[test]
+    fn test_project_conditions() {
+        let query = TaskQuery::new().filter(Condition::Or(vec![
+            Condition::InProject(Some(String::from("Backend"))),
+            Condition::Not(Box::new(Condition::InProject(None))),
+        ]));
+
+        let (sql, values) = query.to_sql();
+
+        assert_eq!(
+            sql,
+            " WHERE (project_id IN (SELECT id FROM projects WHERE name = ?) OR NOT COALESCE(project_id IS NOT NULL, 0)) ORDER BY number ASC"
+        );
+        assert_eq!(values, vec![Value::Text(String::from("Backend"))]);
+    }
+
+    #[test]
+    fn test_blocked_condition() {
+        let query = TaskQuery::new().filter(Condition::Not(Box::new(Condition::Blocked)));
+
+        let (sql, values) = query.to_sql();
+
+        assert!(sql.starts_with(" WHERE NOT COALESCE(EXISTS (SELECT 1 FROM task_dependencies"));
+        assert!(sql.contains("COALESCE(blockers.task_state, 0) != 3"));
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn test_empty_query_orders_by_number() {
+        let (sql, values) = TaskQuery::new().to_sql();
+        assert_eq!(sql, " ORDER BY number ASC");
+        assert!(values.is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::model::error::TaskError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReportColumn {
+    Number,
+    Id,
+    Name,
+    Modification,
+    Term,
+    TaskState,
+    Priority,
+    Description,
+    Tags,
+    Project,
+    DaysLeft,
+    Age,
+}
+
+// The fields of a task in the order of Task::to_vec, the days left and the
+// age are computed when the task is shown.
+pub const DEFAULT_COLUMNS: [ReportColumn; 10] = [
+    ReportColumn::Number,
+    ReportColumn::Id,
+    ReportColumn::Name,
+    ReportColumn::Modification,
+    ReportColumn::Term,
+    ReportColumn::TaskState,
+    ReportColumn::Priority,
+    ReportColumn::Description,
+    ReportColumn::Tags,
+    ReportColumn::Project,
+];
+
+const ALL_COLUMNS: [ReportColumn; 12] = [
+    ReportColumn::Number,
+    ReportColumn::Id,
+    ReportColumn::Name,
+    ReportColumn::Modification,
+    ReportColumn::Term,
+    ReportColumn::TaskState,
+    ReportColumn::Priority,
+    ReportColumn::Description,
+    ReportColumn::Tags,
+    ReportColumn::Project,
+    ReportColumn::DaysLeft,
+    ReportColumn::Age,
+];
+
+impl ReportColumn {
+    pub fn to_str(&self) -> &str {
+        return match self {
+            Self::Number => "number",
+            Self::Id => "id",
+            Self::Name => "name",
+            Self::Modification => "modification",
+            Self::Term => "term",
+            Self::TaskState => "task_state",
+            Self::Priority => "priority",
+            Self::Description => "description",
+            Self::Tags => "tags",
+            Self::Project => "project",
+            Self::DaysLeft => "days_left",
+            Self::Age => "age",
+        };
+    }
+
+    pub fn header(&self) -> &'static str {
+        return match self {
+            Self::Number => "#",
+            Self::Id => "ID",
+            Self::Name => "Name",
+            Self::Modification => "Modification",
+            Self::Term => "Term",
+            Self::TaskState => "Task State",
+            Self::Priority => "Priority",
+            Self::Description => "Description",
+            Self::Tags => "Tags",
+            Self::Project => "Project",
+            Self::DaysLeft => "Days Left",
+            Self::Age => "Age",
+        };
+    }
+
+    pub fn from_string(column: &str) -> Option<ReportColumn> {
+        return match column {
+            "number" | "#" => Some(Self::Number),
+            "id" => Some(Self::Id),
+            "name" => Some(Self::Name),
+            "modification" => Some(Self::Modification),
+            "term" => Some(Self::Term),
+            "task_state" | "state" => Some(Self::TaskState),
+            "priority" => Some(Self::Priority),
+            "description" => Some(Self::Description),
+            "tags" | "tag" => Some(Self::Tags),
+            "project" => Some(Self::Project),
+            "days_left" => Some(Self::DaysLeft),
+            "age" => Some(Self::Age),
+            _ => None,
+        };
+    }
+
+    // Position of the field in Task::to_vec and Task::to_record.
+    pub fn field_index(&self) -> Option<usize> {
+        return DEFAULT_COLUMNS.iter().position(|i| i == self);
+    }
+}
+
+// Parses a list such as "name,term,priority", the order is kept.
+pub fn parse_columns(columns: &str) -> Result<Vec<ReportColumn>, TaskError> {
+    let mut parsed = Vec::new();
+    for i in columns.split(',').map(str::trim) {
+        let column = ReportColumn::from_string(&i.to_lowercase())
+            .ok_or_else(|| TaskError::InvalidColumn(String::from(i)))?;
+        parsed.push(column);
+    }
+    return Ok(parsed);
+}
+
+pub fn columns_to_string(columns: &[ReportColumn]) -> String {
+    return columns
+        .iter()
+        .map(|i| i.to_str())
+        .collect::<Vec<&str>>()
+        .join(",");
+}
+
+pub fn column_names() -> String {
+    return columns_to_string(&ALL_COLUMNS).replace(',', ", ");
+}
+
+#[cfg(test)]
+mod tests_report_column {
+    use super::*;
+
+    #[test]
+    fn test_from_string() {
+        for i in ALL_COLUMNS {
+            assert_eq!(ReportColumn::from_string(i.to_str()), Some(i));
+        }
+        assert_eq!(ReportColumn::from_string("#"), Some(ReportColumn::Number));
+        assert_eq!(ReportColumn::from_string("days"), None);
+    }
+
+    #[test]
+    fn test_parse_columns() {
+        let columns = parse_columns("Name, term,days_left").unwrap();
+
+        assert_eq!(
+            columns,
+            vec![
+                ReportColumn::Name,
+                ReportColumn::Term,
+                ReportColumn::DaysLeft
+            ]
+        );
+        assert_eq!(columns_to_string(&columns), "name,term,days_left");
+        assert!(matches!(
+            parse_columns("name,,term"),
+            Err(TaskError::InvalidColumn(i)) if i.is_empty()
+        ));
+        assert!(matches!(
+            parse_columns("name,due"),
+            Err(TaskError::InvalidColumn(i)) if i == "due"
+        ));
+    }
+
+    #[test]
+    fn test_field_index() {
+        assert_eq!(ReportColumn::Number.field_index(), Some(0));
+        assert_eq!(ReportColumn::Description.field_index(), Some(7));
+        assert_eq!(ReportColumn::Age.field_index(), None);
+    }
+}
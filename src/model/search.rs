@@ -0,0 +1,151 @@
+use crate::model::error::TaskError;
+use crate::Task;
+
+// Snippets mark the matched words with these characters, the view decides
+// how to show them.
+pub const HIGHLIGHT_START: char = '\u{2}';
+pub const HIGHLIGHT_END: char = '\u{3}';
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub task: Task,
+    pub snippet: String,
+}
+
+const OPERATORS: [&str; 3] = ["AND", "OR", "NOT"];
+
+fn search_error(search: &str, position: usize, message: &str) -> TaskError {
+    return TaskError::InvalidQuery {
+        query: String::from(search),
+        position,
+        message: String::from(message),
+    };
+}
+
+fn quote(text: &str) -> String {
+    return format!("\"{}\"", text.replace('"', "\"\""));
+}
+
+// Every word is quoted before it reaches FTS5, so punctuation such as the
+// dots of a host name is searched for instead of read as query syntax. What
+// is kept of that syntax is "a phrase", a trailing * for prefixes and the
+// AND, OR and NOT operators written in capitals.
+pub fn to_match_query(search: &str) -> Result<String, TaskError> {
+    let chars: Vec<char> = search.chars().collect();
+    let mut terms: Vec<String> = Vec::new();
+    let mut expect_term = true;
+    let mut last_operator = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let position = i;
+        let mut text = String::new();
+        let phrase = chars[i] == '"';
+        if phrase {
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                text.push(chars[i]);
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err(search_error(search, position, "unterminated quote"));
+            }
+            i += 1;
+        } else {
+            while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '"' {
+                text.push(chars[i]);
+                i += 1;
+            }
+        }
+        let prefix = if phrase {
+            i < chars.len() && chars[i] == '*'
+        } else {
+            text.ends_with('*')
+        };
+        if phrase && prefix {
+            i += 1;
+        }
+        let text = text.trim_end_matches('*');
+
+        if !phrase && OPERATORS.contains(&text) && !prefix {
+            if expect_term {
+                return Err(search_error(
+                    search,
+                    position,
+                    "expected a word before the operator",
+                ));
+            }
+            terms.push(String::from(text));
+            expect_term = true;
+            last_operator = position;
+            continue;
+        }
+        if text.trim().is_empty() {
+            return Err(search_error(search, position, "expected a word"));
+        }
+        let mut term = quote(text);
+        if prefix {
+            term.push('*');
+        }
+        terms.push(term);
+        expect_term = false;
+    }
+    if terms.is_empty() {
+        return Err(search_error(search, 0, "the search is empty"));
+    }
+    if expect_term {
+        return Err(search_error(
+            search,
+            last_operator,
+            "expected a word after the operator",
+        ));
+    }
+    return Ok(terms.join(" "));
+}
+
+#[cfg(test)]
+mod tests_search {
+    use super::*;
+
+    #[test]
+    fn test_words_are_quoted() {
+        assert_eq!(
+            to_match_query("TLS api.example.com").unwrap(),
+            String::from("\"TLS\" \"api.example.com\"")
+        );
+    }
+
+    #[test]
+    fn test_prefix_phrase_and_operators() {
+        assert_eq!(
+            to_match_query("certif* OR \"renew the cert\"* NOT staging").unwrap(),
+            String::from("\"certif\"* OR \"renew the cert\"* NOT \"staging\"")
+        );
+        assert_eq!(
+            to_match_query("not or").unwrap(),
+            String::from("\"not\" \"or\""),
+            "Only capital operators are operators"
+        );
+    }
+
+    #[test]
+    fn test_invalid_searches() {
+        for (search, position) in [
+            ("", 0),
+            ("OR tls", 0),
+            ("tls AND", 4),
+            ("\"tls certificate", 0),
+            ("tls *", 4),
+        ] {
+            match to_match_query(search) {
+                Err(TaskError::InvalidQuery { position: i, .. }) => {
+                    assert_eq!(i, position, "Wrong position for {:?}", search)
+                }
+                other => panic!("Expected an error for {:?}, got {:?}", search, other),
+            }
+        }
+    }
+}
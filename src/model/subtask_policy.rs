@@ -0,0 +1,57 @@
+use std::fmt::Display;
+
+// What happens to the subtasks of a task that is deleted or ended.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SubtaskPolicy {
+    // Subtasks of a deleted task become top-level tasks, ending a task
+    // leaves them as they are.
+    #[default]
+    Keep,
+    // Subtasks are deleted or ended along with their parent.
+    Cascade,
+    // A task with subtasks cannot be deleted, nor ended while one is open.
+    Refuse,
+}
+
+impl SubtaskPolicy {
+    pub fn to_str(&self) -> &str {
+        return match self {
+            Self::Keep => "keep",
+            Self::Cascade => "cascade",
+            Self::Refuse => "refuse",
+        };
+    }
+
+    pub fn from_string(policy: &str) -> Option<SubtaskPolicy> {
+        return match policy {
+            "keep" => Some(Self::Keep),
+            "cascade" => Some(Self::Cascade),
+            "refuse" => Some(Self::Refuse),
+            _ => None,
+        };
+    }
+}
+
+impl Display for SubtaskPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+#[cfg(test)]
+mod tests_subtask_policy {
+    use super::*;
+
+    #[test]
+    fn test_from_string() {
+        for i in [
+            SubtaskPolicy::Keep,
+            SubtaskPolicy::Cascade,
+            SubtaskPolicy::Refuse,
+        ] {
+            assert_eq!(SubtaskPolicy::from_string(i.to_str()), Some(i));
+        }
+        assert_eq!(SubtaskPolicy::from_string("delete"), None);
+        assert_eq!(SubtaskPolicy::default(), SubtaskPolicy::Keep);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::model::error::TaskError;
+
+fn is_tag_char(i: char) -> bool {
+    return i.is_alphanumeric() || i == '-' || i == '_';
+}
+
+// Tags are compared in lowercase so "UI" and "ui" are the same tag, a
+// leading "+" is accepted as Taskwarrior writes them.
+pub fn parse_tag(tag: &str) -> Result<String, TaskError> {
+    let tag = tag.trim();
+    let tag = tag.strip_prefix('+').unwrap_or(tag).to_lowercase();
+    if tag.is_empty() || !tag.chars().all(is_tag_char) {
+        return Err(TaskError::InvalidTag(tag));
+    }
+    return Ok(tag);
+}
+
+// A list separated by spaces or commas, as the Tags column is written.
+pub fn parse_tags(tags: &str) -> Result<Vec<String>, TaskError> {
+    let parsed = tags
+        .split(|i: char| i.is_whitespace() || i == ',')
+        .filter(|i| !i.is_empty())
+        .map(parse_tag)
+        .collect::<Result<Vec<String>, TaskError>>()?;
+    return Ok(normalize(parsed));
+}
+
+pub fn normalize(mut tags: Vec<String>) -> Vec<String> {
+    tags.sort();
+    tags.dedup();
+    return tags;
+}
+
+#[cfg(test)]
+mod tests_tag {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(parse_tag(" +Backend ").unwrap(), "backend");
+        assert_eq!(parse_tag("ui_v2-beta").unwrap(), "ui_v2-beta");
+        assert_eq!(parse_tag("café").unwrap(), "café");
+        for i in ["", "+", "two words", "a,b", "a:b"] {
+            assert!(
+                matches!(parse_tag(i), Err(TaskError::InvalidTag(_))),
+                "{:?} should be refused",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(
+            parse_tags("ui, backend ui\tdocs").unwrap(),
+            vec!["backend", "docs", "ui"]
+        );
+        assert!(parse_tags("").unwrap().is_empty());
+        assert!(parse_tags("ui a:b").is_err());
+    }
+}
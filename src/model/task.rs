@@ -1,18 +1,88 @@
+use chrono::Local;
 use rand;
-use rusqlite::{params, Connection};
-use std::error::Error;
+use rusqlite::{
+    params, params_from_iter, types::Type, Connection, ErrorCode, OpenFlags, OptionalExtension, Row,
+};
+use serde::{Serialize, Serializer};
 use std::fs;
-
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::model::error::TaskError;
+use crate::model::migration;
+use crate::model::name_policy::NamePolicy;
+use crate::model::project::{Project, ProjectSummary};
+use crate::model::query::{self, TaskQuery};
+use crate::model::report_column::{self, ReportColumn, DEFAULT_COLUMNS};
+use crate::model::search::{self, SearchHit, HIGHLIGHT_END, HIGHLIGHT_START};
+use crate::model::subtask_policy::SubtaskPolicy;
+use crate::model::tag;
 use crate::Date;
 use crate::Priority;
 use crate::TaskState;
 
-pub const DATABASE_PATH: &str = "./.todo_list/info.db3";
-pub const DATABASE_DIR_PATH: &str = ".todo_list";
+pub fn init_folder(database_path: &Path) -> Result<(), TaskError> {
+    if let Some(i) = database_path.parent() {
+        fs::create_dir_all(i)?;
+    }
+    return Ok(());
+}
+
+const JOURNAL_SUFFIXES: [&str; 3] = ["-journal", "-wal", "-shm"];
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_os_string();
+    path.push(suffix);
+    return PathBuf::from(path);
+}
+
+// Two backups in the same second get a counter, as rename would replace the
+// first one and lose its tasks.
+fn backup_path(database_path: &Path) -> Result<PathBuf, TaskError> {
+    let stamp = Local::now().format("%Y%m%d%H%M%S").to_string();
+    for i in 0..1000 {
+        let suffix = if i == 0 {
+            format!(".{}.bak", stamp)
+        } else {
+            format!(".{}-{}.bak", stamp, i)
+        };
+        let backup = with_suffix(database_path, &suffix);
+        let taken = backup.exists()
+            || JOURNAL_SUFFIXES
+                .iter()
+                .any(|j| with_suffix(&backup, j).exists());
+        if !taken {
+            return Ok(backup);
+        }
+    }
+    return Err(TaskError::Io(std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!("no free backup name for {}", database_path.display()),
+    )));
+}
+
+// The journal files of SQLite belong to the database, so they are moved
+// along with it or the backup could not be opened again. Nothing is moved
+// over an existing file.
+pub fn backup_database(database_path: &Path) -> Result<PathBuf, TaskError> {
+    let backup = backup_path(database_path)?;
+    // Another process may have taken the name since it was chosen.
+    if backup.exists() {
+        return Err(TaskError::Io(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("the backup {} already exists", backup.display()),
+        )));
+    }
+    fs::rename(database_path, &backup)?;
 
-pub fn init_folder() {
-    fs::create_dir(DATABASE_DIR_PATH)
-        .expect("Was not possible to create the directory for de database");
+    for i in JOURNAL_SUFFIXES {
+        let companion = with_suffix(database_path, i);
+        if companion.exists() {
+            fs::rename(&companion, with_suffix(&backup, i))?;
+        }
+    }
+    return Ok(backup);
 }
 
 pub trait TaskBuilder {
@@ -23,7 +93,11 @@ pub trait TaskBuilder {
     fn set_description(&self, description: String) -> Self;
     fn set_name(&self, name: String) -> Self;
     fn set_term(&self, date: Date) -> Self;
-    fn set_by_task(&self, task: Task) -> Self;
+    fn set_modification(&self, date: Date) -> Self;
+    fn set_uuid(&self, uuid: String) -> Self;
+    fn set_tags(&self, tags: Vec<String>) -> Self;
+    fn set_project(&self, project: String) -> Self;
+    fn set_parent(&self, parent_id: u64) -> Self;
     fn get_task(&mut self) -> Task;
 }
 
@@ -37,6 +111,7 @@ impl TaskBuilder for ConcreteTaskBuilder {
         let task_builder = ConcreteTaskBuilder {
             task: Task {
                 id,
+                number: None,
                 name: format!("TASK:{}", id),
                 description: None,
                 modification: Date {
@@ -47,6 +122,11 @@ impl TaskBuilder for ConcreteTaskBuilder {
                 term: None,
                 task_state: None,
                 priority: None,
+                uuid: None,
+                tags: Vec::new(),
+                project: None,
+                parent: None,
+                blockers: Vec::new(),
             },
         };
         return task_builder;
@@ -85,56 +165,105 @@ impl TaskBuilder for ConcreteTaskBuilder {
         new_task.term = Some(date);
         return ConcreteTaskBuilder { task: new_task };
     }
-    fn set_by_task(&self, task: Task) -> Self {
-        let mut old_task = self.task.clone();
-        old_task.id = task.id.clone();
-        old_task.name = task.name.clone();
-        if let Some(_) = task.description {
-            old_task.description = task.description.clone();
-        }
-        if let Some(_) = task.term {
-            old_task.term = task.term.clone();
-        }
-        if let Some(_) = task.task_state {
-            old_task.task_state = task.task_state.clone();
-        }
-        if let Some(_) = task.priority {
-            old_task.priority = task.priority.clone();
-        }
 
-        return ConcreteTaskBuilder { task: old_task };
+    fn set_modification(&self, date: Date) -> Self {
+        let mut new_task = self.task.clone();
+        new_task.modification = date;
+        return ConcreteTaskBuilder { task: new_task };
+    }
+
+    fn set_uuid(&self, uuid: String) -> Self {
+        let mut new_task = self.task.clone();
+        new_task.uuid = Some(uuid);
+        return ConcreteTaskBuilder { task: new_task };
+    }
+
+    fn set_tags(&self, tags: Vec<String>) -> Self {
+        let mut new_task = self.task.clone();
+        new_task.tags = tag::normalize(tags);
+        return ConcreteTaskBuilder { task: new_task };
+    }
+
+    fn set_project(&self, project: String) -> Self {
+        let mut new_task = self.task.clone();
+        new_task.project = Some(project);
+        return ConcreteTaskBuilder { task: new_task };
+    }
+
+    fn set_parent(&self, parent_id: u64) -> Self {
+        let mut new_task = self.task.clone();
+        new_task.parent = Some(parent_id);
+        return ConcreteTaskBuilder { task: new_task };
     }
+
+    // The zero date means set_modification was not called, so the task is
+    // new today.
     fn get_task(&mut self) -> Task {
         let mut new_task = self.task.clone();
-        new_task.modification = Date::get_local_date();
+        if new_task.modification.year == 0 {
+            new_task.modification = Date::get_local_date();
+        }
         self.reset();
         self.task = Task::new();
         return new_task;
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+// The field names are part of the JSON output, see docs/task-output.schema.json
+// before renaming or removing one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Task {
+    #[serde(serialize_with = "serialize_id")]
     id: u64,
+    number: Option<u64>,
     name: String,
     description: Option<String>,
     modification: Date,
     term: Option<Date>,
     task_state: Option<TaskState>,
     priority: Option<Priority>,
+    // Identifies the task in other tools, such as Taskwarrior, so importing
+    // their tasks again updates them.
+    uuid: Option<String>,
+    // Sorted and without repeats.
+    tags: Vec<String>,
+    // The name of the project, stored as the id of its row.
+    project: Option<String>,
+    // The id of the task this one is a subtask of.
+    #[serde(serialize_with = "serialize_parent")]
+    parent: Option<u64>,
+    // The ids of the tasks that block this one and have not ended.
+    #[serde(rename = "blocked_by", serialize_with = "serialize_ids")]
+    blockers: Vec<u64>,
 }
 
-fn description_from_string(description: String) -> Option<String> {
-    match description.as_str() {
-        "None" => None,
-        _ => Some(description),
-    }
+// JSON numbers above 2^53 lose precision in most parsers, so the id is
+// written as a string.
+fn serialize_id<S: Serializer>(id: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    return serializer.serialize_str(&id.to_string());
+}
+
+fn serialize_ids<S: Serializer>(ids: &[u64], serializer: S) -> Result<S::Ok, S::Error> {
+    return serializer.collect_seq(ids.iter().map(|i| i.to_string()));
+}
+
+fn serialize_parent<S: Serializer>(id: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+    return match id {
+        Some(i) => serialize_id(i, serializer),
+        None => serializer.serialize_none(),
+    };
 }
 
 impl Task {
     pub fn get_id(&self) -> u64 {
         return self.id;
     }
+    pub fn get_number(&self) -> String {
+        return match self.number {
+            Some(i) => i.to_string(),
+            None => String::from("None"),
+        };
+    }
     pub fn get_name(&self) -> String {
         return self.name.clone();
     }
@@ -166,11 +295,71 @@ impl Task {
             None => String::from("None"),
         };
     }
+    pub fn get_tags(&self) -> String {
+        if self.tags.is_empty() {
+            return String::from("None");
+        }
+        return self.tags.join(" ");
+    }
+    pub fn get_project(&self) -> String {
+        return match self.project.clone() {
+            Some(i) => i,
+            None => String::from("None"),
+        };
+    }
+
+    // Typed values for the formats that write each field their own way.
+    pub fn number(&self) -> Option<u64> {
+        return self.number;
+    }
+    pub fn description(&self) -> Option<&str> {
+        return self.description.as_deref();
+    }
+    pub fn modification(&self) -> &Date {
+        return &self.modification;
+    }
+    pub fn term(&self) -> Option<&Date> {
+        return self.term.as_ref();
+    }
+    pub fn task_state(&self) -> Option<&TaskState> {
+        return self.task_state.as_ref();
+    }
+    pub fn priority(&self) -> Option<&Priority> {
+        return self.priority.as_ref();
+    }
+    pub fn uuid(&self) -> Option<&str> {
+        return self.uuid.as_deref();
+    }
+    pub fn tags(&self) -> &[String] {
+        return &self.tags;
+    }
+    pub fn project(&self) -> Option<&str> {
+        return self.project.as_deref();
+    }
+    pub fn parent(&self) -> Option<u64> {
+        return self.parent;
+    }
+    pub fn blockers(&self) -> &[u64] {
+        return &self.blockers;
+    }
+    pub fn is_blocked(&self) -> bool {
+        return !self.blockers.is_empty();
+    }
+
+    // The table marks a task waiting on a blocker next to its state.
+    fn get_state_mark(&self) -> String {
+        return match (&self.task_state, self.is_blocked()) {
+            (_, false) => self.get_state(),
+            (None, true) => String::from("blocked"),
+            (Some(i), true) => format!("{} (blocked)", i),
+        };
+    }
 
     fn new() -> Self {
         let id = rand::random::<u64>();
         return Task {
             id,
+            number: None,
             name: format!("TASK:{}", id),
             description: None,
             modification: Date {
@@ -181,20 +370,172 @@ impl Task {
             term: None,
             task_state: None,
             priority: None,
+            uuid: None,
+            tags: Vec::new(),
+            project: None,
+            parent: None,
+            blockers: Vec::new(),
         };
     }
 
     pub fn to_vec(&self) -> Vec<String> {
-        let mut infos: Vec<String> = Vec::new();
-        infos.push(self.get_id().to_string());
-        infos.push(self.get_name());
-        infos.push(self.get_modification());
-        infos.push(self.get_term());
-        infos.push(self.get_state());
-        infos.push(self.get_priority());
-        infos.push(self.get_description());
+        return vec![
+            self.get_number(),
+            self.get_id().to_string(),
+            self.get_name(),
+            self.get_modification(),
+            self.get_term(),
+            self.get_state_mark(),
+            self.get_priority(),
+            self.get_description(),
+            self.get_tags(),
+            self.get_project(),
+        ];
+    }
+
+    // Same columns as to_vec, for files read by other programs: absent fields
+    // are empty and dates are ISO.
+    pub fn to_record(&self) -> Vec<String> {
+        let optional = |i: Option<String>| i.unwrap_or_default();
+        return vec![
+            optional(self.number.map(|i| i.to_string())),
+            self.id.to_string(),
+            self.name.clone(),
+            self.modification.to_iso_string(),
+            optional(self.term.as_ref().map(|i| i.to_iso_string())),
+            optional(self.task_state.as_ref().map(|i| i.to_string())),
+            optional(self.priority.as_ref().map(|i| i.to_string())),
+            optional(self.description.clone()),
+            self.tags.join(" "),
+            optional(self.project.clone()),
+        ];
+    }
+}
+
+// Each field is None when it must be kept, and Some(None) when an optional
+// field must be cleared. Tags are replaced first, then added and removed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskUpdate {
+    name: Option<String>,
+    description: Option<Option<String>>,
+    term: Option<Option<Date>>,
+    task_state: Option<Option<TaskState>>,
+    priority: Option<Option<Priority>>,
+    modification: Option<Date>,
+    tags: Option<Vec<String>>,
+    added_tags: Vec<String>,
+    removed_tags: Vec<String>,
+    project: Option<Option<String>>,
+    parent: Option<Option<u64>>,
+}
+
+impl TaskUpdate {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    // Replaces every field with the ones of the task, keeping its date, for
+    // a task read again from another tool.
+    pub fn from_task(task: &Task) -> Self {
+        return Self::new()
+            .set_name(task.name.clone())
+            .set_description(task.description.clone())
+            .set_term(task.term.clone())
+            .set_task_state(task.task_state.clone())
+            .set_priority(task.priority.clone())
+            .set_modification(task.modification.clone())
+            .set_tags(task.tags.clone())
+            .set_project(task.project.clone());
+    }
+
+    pub fn set_name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        return self;
+    }
+
+    pub fn set_description(mut self, description: Option<String>) -> Self {
+        self.description = Some(description);
+        return self;
+    }
+
+    pub fn set_term(mut self, term: Option<Date>) -> Self {
+        self.term = Some(term);
+        return self;
+    }
+
+    pub fn set_task_state(mut self, task_state: Option<TaskState>) -> Self {
+        self.task_state = Some(task_state);
+        return self;
+    }
+
+    pub fn set_priority(mut self, priority: Option<Priority>) -> Self {
+        self.priority = Some(priority);
+        return self;
+    }
+
+    pub fn set_modification(mut self, modification: Date) -> Self {
+        self.modification = Some(modification);
+        return self;
+    }
+
+    pub fn set_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = Some(tags);
+        return self;
+    }
+
+    pub fn set_project(mut self, project: Option<String>) -> Self {
+        self.project = Some(project);
+        return self;
+    }
+
+    pub fn set_parent(mut self, parent_id: Option<u64>) -> Self {
+        self.parent = Some(parent_id);
+        return self;
+    }
+
+    pub fn add_tag(mut self, tag: String) -> Self {
+        self.added_tags.push(tag);
+        return self;
+    }
+
+    pub fn remove_tag(mut self, tag: String) -> Self {
+        self.removed_tags.push(tag);
+        return self;
+    }
 
-        return infos;
+    pub fn apply(&self, task: &Task) -> Task {
+        let mut new_task = task.clone();
+        if let Some(i) = &self.name {
+            new_task.name = i.clone();
+        }
+        if let Some(i) = &self.description {
+            new_task.description = i.clone();
+        }
+        if let Some(i) = &self.term {
+            new_task.term = i.clone();
+        }
+        if let Some(i) = &self.task_state {
+            new_task.task_state = i.clone();
+        }
+        if let Some(i) = &self.priority {
+            new_task.priority = i.clone();
+        }
+        if let Some(i) = &self.modification {
+            new_task.modification = i.clone();
+        }
+        if let Some(i) = &self.tags {
+            new_task.tags = i.clone();
+        }
+        new_task.tags.extend(self.added_tags.iter().cloned());
+        new_task.tags.retain(|i| !self.removed_tags.contains(i));
+        new_task.tags = tag::normalize(new_task.tags);
+        if let Some(i) = &self.project {
+            new_task.project = i.clone();
+        }
+        if let Some(i) = self.parent {
+            new_task.parent = i;
+        }
+        return new_task;
     }
 }
 
@@ -202,6 +543,26 @@ impl Task {
 mod tests_task {
     use super::*;
 
+    #[test]
+    fn test_serialize_json() {
+        let mut task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Deploy"))
+            .set_priority(Priority::High)
+            .set_term(Date::from_iso_string("2025-03-01").unwrap())
+            .set_project(String::from("Ops"))
+            .get_task();
+        task.id = u64::MAX;
+        task.number = Some(4);
+        task.modification = Date::from_iso_string("2025-01-02").unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&task).unwrap(),
+            String::from(
+                "{\"id\":\"18446744073709551615\",\"number\":4,\"name\":\"Deploy\",\"description\":null,\"modification\":\"2025-01-02\",\"term\":\"2025-03-01\",\"task_state\":null,\"priority\":\"high\",\"uuid\":null,\"tags\":[],\"project\":\"Ops\",\"parent\":null,\"blocked_by\":[]}"
+            )
+        );
+    }
+
     #[test]
     fn test_creation_random_id() {
         let mut constructor = ConcreteTaskBuilder::new()
@@ -266,6 +627,18 @@ mod tests_task {
         );
     }
 
+    #[test]
+    fn test_set_modification() {
+        let date = Date::from_iso_string("2024-05-06").unwrap();
+        let mut builder = ConcreteTaskBuilder::new().set_modification(date.clone());
+        assert_eq!(builder.get_task().modification, date);
+        assert_eq!(
+            builder.get_task().modification,
+            Date::get_local_date(),
+            "The next task is new again"
+        );
+    }
+
     #[test]
     fn test_get_term() {
         let date = String::from("1-12-2000");
@@ -564,269 +937,1232 @@ mod tests_task {
 }
 
 pub trait TaskRelationalManager {
-    fn insert_task(&self, task: &Task) -> Result<(), Box<dyn Error>>;
-    fn get_tasks(&self) -> Option<Vec<Task>>;
-    fn get_task_by_id(&self, task_id: u64) -> Option<Task>;
-    fn get_task_by_name(&self, task_name: &String) -> Option<Task>;
-    fn delete_task_by_name(&self, task_name: &String) -> Result<(), Box<dyn Error>>;
-    fn delete_task_by_id(&self, task_id: u64) -> Result<(), Box<dyn Error>>;
+    fn insert_task(&self, task: &Task) -> Result<u64, TaskError>;
+    fn query_tasks(&self, query: &TaskQuery) -> Result<Vec<Task>, TaskError>;
+    fn search_tasks(&self, search: &str, limit: u64) -> Result<Vec<SearchHit>, TaskError>;
+    fn get_task_by_id(&self, task_id: u64) -> Result<Task, TaskError>;
+    fn get_task_by_number(&self, task_number: u64) -> Result<Task, TaskError>;
+    fn get_task_by_uuid(&self, uuid: &str) -> Result<Task, TaskError>;
+    fn get_task_by_name(&self, task_name: &str) -> Result<Task, TaskError>;
+    fn get_tasks_by_name(&self, task_name: &str) -> Result<Vec<Task>, TaskError>;
+    fn delete_task_by_name(&self, task_name: &str, all: bool) -> Result<usize, TaskError>;
+    fn delete_task_by_id(&self, task_id: u64) -> Result<(), TaskError>;
+    fn delete_tasks(&self, query: &TaskQuery) -> Result<usize, TaskError>;
+    fn update_task(&self, task_id: u64, update: &TaskUpdate) -> Result<bool, TaskError>;
+    fn update_tasks(&self, task_ids: &[u64], update: &TaskUpdate) -> Result<usize, TaskError>;
+    fn get_name_policy(&self) -> Result<NamePolicy, TaskError>;
+    fn set_name_policy(&self, policy: &NamePolicy) -> Result<(), TaskError>;
+    fn get_columns(&self) -> Result<Vec<ReportColumn>, TaskError>;
+    fn get_subtask_policy(&self) -> Result<SubtaskPolicy, TaskError>;
+    fn add_dependency(&self, blocker_id: u64, blocked_id: u64) -> Result<bool, TaskError>;
+    fn remove_dependency(&self, blocker_id: u64, blocked_id: u64) -> Result<bool, TaskError>;
+    fn list_dependencies(&self) -> Result<Vec<(u64, u64)>, TaskError>;
+    fn set_subtask_policy(&self, policy: &SubtaskPolicy) -> Result<(), TaskError>;
+    fn list_tags(&self) -> Result<Vec<(String, u64)>, TaskError>;
+    fn set_columns(&self, columns: &[ReportColumn]) -> Result<(), TaskError>;
+    fn compact_numbers(&self) -> Result<u64, TaskError>;
+    fn assign_uuids(&self) -> Result<u64, TaskError>;
+    fn create_project(&self, project: &Project) -> Result<(), TaskError>;
+    fn get_project(&self, name: &str) -> Result<Project, TaskError>;
+    fn list_projects(&self, archived: bool) -> Result<Vec<ProjectSummary>, TaskError>;
+    fn rename_project(&self, name: &str, new_name: &str) -> Result<(), TaskError>;
+    fn archive_project(&self, name: &str, archived: bool) -> Result<(), TaskError>;
+    fn delete_project(
+        &self,
+        name: &str,
+        reassign: Option<Option<&str>>,
+    ) -> Result<usize, TaskError>;
 }
 
 pub struct ConcreteTaskRelationalManager {
     conn: Connection,
+    options: ConnectionOptions,
 }
 
-impl TaskRelationalManager for ConcreteTaskRelationalManager {
-    fn insert_task(&self, task: &Task) -> Result<(), Box<dyn Error>> {
-        let parameters = params![
-            task.get_id().to_string(),
-            task.get_name(),
-            task.get_description(),
-            task.get_modification(),
-            task.get_term(),
-            task.get_state(),
-            task.get_priority()
-        ];
-
-        self.conn.execute(
-            "INSERT INTO tasks(id, name, description, date, term, task_state,priority) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            parameters
-        ).expect("There was an error trying to insert");
+#[derive(Debug, Clone)]
+pub struct ConnectionOptions {
+    pub busy_timeout: Duration,
+    pub retries: u32,
+    pub backoff: Duration,
+    pub create: bool,
+}
 
-        return Ok(());
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        return ConnectionOptions {
+            busy_timeout: Duration::from_millis(5000),
+            retries: 3,
+            backoff: Duration::from_millis(50),
+            create: false,
+        };
     }
+}
 
-    fn get_tasks(&self) -> Option<Vec<Task>> {
-        let mut select_tasks = self.conn.prepare("SELECT * FROM tasks").unwrap();
-        let tasks_iter = select_tasks
-            .query_map([], |row| {
-                let id: String = row.get(0).unwrap();
-                let name: String = row.get(1).unwrap();
-                let description: String = row.get(2).unwrap();
-                let date: String = row.get(3).unwrap();
-                let term: String = row.get(4).unwrap();
-                let task_state: String = row.get(5).unwrap();
-                let priority: String = row.get(6).unwrap();
-                let task = Task {
-                    id: id.parse().unwrap(),
-                    name,
-                    description: description_from_string(description),
-                    modification: Date::from_string(date).unwrap().unwrap(),
-                    term: Date::from_string(term).unwrap(),
-                    task_state: TaskState::from_string(&task_state),
-                    priority: Priority::from_string(&priority),
-                };
-                return Ok(task);
-            })
-            .unwrap();
-        let mut tasks_vec = Vec::new();
-        for i in tasks_iter {
-            tasks_vec.push(i.unwrap());
-        }
-        return Some(tasks_vec);
-    }
+fn is_busy(err: &TaskError) -> bool {
+    let sqlite_err = match err {
+        TaskError::Storage(i) => i,
+        TaskError::Migration { source, .. } => source,
+        _ => return false,
+    };
+    return matches!(
+        sqlite_err.sqlite_error_code(),
+        Some(ErrorCode::DatabaseBusy) | Some(ErrorCode::DatabaseLocked)
+    );
+}
 
-    fn get_task_by_id(&self, task_id: u64) -> Option<Task> {
-        let mut select_tasks = self
-            .conn
-            .prepare("SELECT * FROM tasks WHERE id = ?1")
-            .unwrap();
-        let tasks_iter = select_tasks
-            .query_map([task_id.to_string()], |row| {
-                let id: String = row.get(0).unwrap();
-                let name: String = row.get(1).unwrap();
-                let description: String = row.get(2).unwrap();
-                let date: String = row.get(3).unwrap();
-                let term: String = row.get(4).unwrap();
-                let task_state: String = row.get(5).unwrap();
-                let priority: String = row.get(6).unwrap();
-                let task = Task {
-                    id: id.parse().unwrap(),
-                    name,
-                    description: description_from_string(description),
-                    modification: Date::from_string(date).unwrap().unwrap(),
-                    term: Date::from_string(term).unwrap(),
-                    task_state: TaskState::from_string(&task_state),
-                    priority: Priority::from_string(&priority),
-                };
-                return Ok(task);
-            })
-            .unwrap();
-        let mut tasks_vec = Vec::new();
-        for i in tasks_iter {
-            tasks_vec.push(i.unwrap());
-        }
+const TASK_COLUMNS: &str =
+    "id, name, description, modification, term, task_state, priority, number, uuid";
 
-        if tasks_vec.is_empty() {
-            return None;
-        }
+// The tags of the task in one text, sorted and separated by spaces.
+const TAGS_COLUMN: &str = "(SELECT group_concat(name, ' ') FROM (
+    SELECT tags.name FROM task_tags JOIN tags ON tags.id = task_tags.tag_id
+        WHERE task_tags.task_id = tasks.id ORDER BY tags.name
+))";
 
-        return Some(tasks_vec[0].clone());
-    }
+const PROJECT_COLUMN: &str = "(SELECT name FROM projects WHERE projects.id = tasks.project_id)";
 
-    fn get_task_by_name(&self, task_name: &String) -> Option<Task> {
-        let mut select_tasks = self
-            .conn
-            .prepare("SELECT * FROM tasks WHERE name = ?1")
-            .unwrap();
-        let tasks_iter = select_tasks
-            .query_map([task_name.clone()], |row| {
-                let id: String = row.get(0).unwrap();
-                let name: String = row.get(1).unwrap();
-                let description: String = row.get(2).unwrap();
-                let date: String = row.get(3).unwrap();
-                let term: String = row.get(4).unwrap();
-                let task_state: String = row.get(5).unwrap();
-                let priority: String = row.get(6).unwrap();
-                let task = Task {
-                    id: id.parse().unwrap(),
-                    name,
-                    description: description_from_string(description),
-                    modification: Date::from_string(date).unwrap().unwrap(),
-                    term: Date::from_string(term).unwrap(),
-                    task_state: TaskState::from_string(&task_state),
-                    priority: Priority::from_string(&priority),
-                };
-                return Ok(task);
-            })
-            .unwrap();
-        let mut tasks_vec = Vec::new();
-        for i in tasks_iter {
-            tasks_vec.push(i.unwrap());
-        }
+fn blockers_column() -> String {
+    return format!(
+        "(SELECT group_concat(id, ' ') FROM (
+            SELECT blockers.id {} ORDER BY blockers.number
+        ))",
+        query::open_blockers()
+    );
+}
 
-        if tasks_vec.is_empty() {
-            return None;
-        }
+fn select_columns() -> String {
+    return format!(
+        "{}, {}, {}, tasks.parent_id, {}",
+        TASK_COLUMNS,
+        TAGS_COLUMN,
+        PROJECT_COLUMN,
+        blockers_column()
+    );
+}
+
+fn task_from_row(row: &Row) -> rusqlite::Result<Task> {
+    let id: i64 = row.get(0)?;
+    let number: Option<i64> = row.get(7)?;
+    let tags: Option<String> = row.get(9)?;
+    let blockers: Option<String> = row.get(12)?;
+    let blockers = blockers
+        .map(|i| {
+            i.split(' ')
+                .map(str::parse)
+                .collect::<Result<Vec<i64>, _>>()
+        })
+        .transpose()
+        .map_err(|i| rusqlite::Error::FromSqlConversionFailure(12, Type::Text, Box::new(i)))?
+        .unwrap_or_default();
+    return Ok(Task {
+        id: id as u64,
+        number: number.map(|i| i as u64),
+        name: row.get(1)?,
+        description: row.get(2)?,
+        modification: row.get(3)?,
+        term: row.get(4)?,
+        task_state: row.get(5)?,
+        priority: row.get(6)?,
+        uuid: row.get(8)?,
+        tags: tags
+            .map(|i| i.split(' ').map(String::from).collect())
+            .unwrap_or_default(),
+        project: row.get(10)?,
+        parent: row.get::<_, Option<i64>>(11)?.map(|i| i as u64),
+        blockers: blockers.into_iter().map(|i| i as u64).collect(),
+    });
+}
 
-        return Some(tasks_vec[0].clone());
+fn write_tags(conn: &Connection, task_id: u64, tags: &[String]) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM task_tags WHERE task_id = ?1", [task_id as i64])?;
+    for i in tags {
+        conn.execute(
+            "INSERT INTO tags(name) VALUES (?1) ON CONFLICT(name) DO NOTHING",
+            [i],
+        )?;
+        conn.execute(
+            "INSERT INTO task_tags(task_id, tag_id) SELECT ?1, id FROM tags WHERE name = ?2",
+            params![task_id as i64, i],
+        )?;
     }
+    return Ok(());
+}
 
-    fn delete_task_by_name(&self, task_name: &String) -> Result<(), Box<dyn Error>> {
-        let deleted_tasks = self.conn.execute(
-            "DELETE FROM tasks WHERE name = ?1",
-            params![task_name.clone()],
-        );
-        match deleted_tasks {
-            Ok(i) if i > 0 => return Ok(()),
-            _ => Err("The task neither exists or it was not able to delete".into()),
+// Tasks can only be moved to a project that exists and is not archived.
+fn write_project(conn: &Connection, task_id: u64, project: Option<&str>) -> Result<(), TaskError> {
+    let project_id = match project {
+        Some(i) => {
+            let (id, archived) = conn
+                .query_row(
+                    "SELECT id, archived FROM projects WHERE name = ?1",
+                    [i],
+                    |row| Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?)),
+                )
+                .optional()?
+                .ok_or_else(|| TaskError::ProjectNotFound(String::from(i)))?;
+            if archived {
+                return Err(TaskError::ArchivedProject(String::from(i)));
+            }
+            Some(id)
         }
-    }
+        None => None,
+    };
+    conn.execute(
+        "UPDATE tasks SET project_id = ?2 WHERE id = ?1",
+        params![task_id as i64, project_id],
+    )?;
+    return Ok(());
+}
 
-    fn delete_task_by_id(&self, task_id: u64) -> Result<(), Box<dyn Error>> {
-        let deleted_tasks = self
-            .conn
-            .execute("DELETE FROM tasks WHERE id = ?1", params![task_id]);
-        match deleted_tasks {
-            Ok(i) if i > 0 => return Ok(()),
-            _ => Err("The task neither exists or it was not able to delete".into()),
+fn task_number(conn: &Connection, task_id: u64) -> Result<String, TaskError> {
+    let number = conn
+        .query_row(
+            "SELECT number FROM tasks WHERE id = ?1",
+            [task_id as i64],
+            |row| row.get::<_, Option<i64>>(0),
+        )
+        .optional()?
+        .ok_or_else(|| TaskError::NotFound(format!("id {}", task_id)))?;
+    return Ok(number
+        .map(|i| i.to_string())
+        .unwrap_or_else(|| String::from("None")));
+}
+
+// A task cannot be a subtask of itself or of one of its own subtasks. UNION
+// drops repeated rows, so the walk ends even if the table had a cycle.
+fn write_parent(conn: &Connection, task_id: u64, parent: Option<u64>) -> Result<(), TaskError> {
+    if let Some(i) = parent {
+        let parent_number = task_number(conn, i)?;
+        let cycle = conn
+            .query_row(
+                "WITH RECURSIVE ancestors(id) AS (
+                    SELECT ?1
+                    UNION SELECT tasks.parent_id FROM tasks JOIN ancestors ON tasks.id = ancestors.id
+                        WHERE tasks.parent_id IS NOT NULL
+                )
+                SELECT 1 FROM ancestors WHERE id = ?2",
+                params![i as i64, task_id as i64],
+                |_| Ok(()),
+            )
+            .optional()?;
+        if cycle.is_some() {
+            return Err(TaskError::SubtaskCycle {
+                task: task_number(conn, task_id)?,
+                parent: parent_number,
+            });
         }
     }
+    conn.execute(
+        "UPDATE tasks SET parent_id = ?2 WHERE id = ?1",
+        params![task_id as i64, parent.map(|i| i as i64)],
+    )?;
+    return Ok(());
 }
 
-impl ConcreteTaskRelationalManager {
-    pub fn new(path: &str) -> Self {
-        let mut conn = Connection::open(path);
-        loop {
-            match conn {
-                Ok(i) => {
-                    return Self { conn: i };
+// Every task below the given ones, at any depth.
+fn subtasks_of(conn: &Connection, task_ids: &[i64]) -> rusqlite::Result<Vec<i64>> {
+    let placeholders = vec!["?"; task_ids.len()].join(", ");
+    let mut select = conn.prepare(
+        format!(
+            "WITH RECURSIVE subtasks(id) AS (
+                SELECT id FROM tasks WHERE parent_id IN ({})
+                UNION SELECT tasks.id FROM tasks JOIN subtasks ON tasks.parent_id = subtasks.id
+            )
+            SELECT id FROM subtasks",
+            placeholders
+        )
+        .as_str(),
+    )?;
+    let subtasks = select
+        .query_map(params_from_iter(task_ids.iter()), |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<i64>>>()?;
+    return Ok(subtasks);
+}
+
+// Deletes the tasks and, with the cascade policy, their subtasks. With the
+// keep policy the trigger of the parent column makes the subtasks top-level.
+fn delete_with_subtasks(conn: &Connection, task_ids: &[i64]) -> Result<usize, TaskError> {
+    if task_ids.is_empty() {
+        return Ok(0);
+    }
+    let mut deleted_ids = task_ids.to_vec();
+    match read_subtask_policy(conn)? {
+        SubtaskPolicy::Keep => {}
+        SubtaskPolicy::Cascade => deleted_ids.extend(subtasks_of(conn, task_ids)?),
+        SubtaskPolicy::Refuse => {
+            for i in task_ids {
+                let left = subtasks_of(conn, &[*i])?
+                    .iter()
+                    .filter(|j| !task_ids.contains(j))
+                    .count();
+                if left > 0 {
+                    return Err(TaskError::HasSubtasks {
+                        task: task_number(conn, *i as u64)?,
+                        subtasks: left,
+                        ending: false,
+                    });
                 }
-                Err(_) => conn = Connection::open(path),
             }
         }
     }
+    deleted_ids.sort();
+    deleted_ids.dedup();
+    let placeholders = vec!["?"; deleted_ids.len()].join(", ");
+    let deleted_tasks = conn.execute(
+        format!("DELETE FROM tasks WHERE id IN ({})", placeholders).as_str(),
+        params_from_iter(deleted_ids.iter()),
+    )?;
+    return Ok(deleted_tasks);
+}
 
-    pub fn init_db(&self) {
-        self.conn.execute("CREATE TABLE IF NOT EXISTS tasks(id TEXT PRIMARY KEY, name TEXT, description TEXT, date TEXT, term TEXT, task_state TEXT, priority TEXT)", []).unwrap();
+// Ending a task ends its open subtasks with the cascade policy, and is
+// refused while one is open with the refuse policy.
+fn end_subtasks(conn: &Connection, task_id: u64, modification: &Date) -> Result<(), TaskError> {
+    let policy = read_subtask_policy(conn)?;
+    if policy == SubtaskPolicy::Keep {
+        return Ok(());
     }
-
-    pub fn check_table(&self, table_name: &str) -> Result<i64, Box<dyn Error>> {
-        let mut stmt = self
-            .conn
-            .prepare(
-                "SELECT COUNT (*)
-                FROM sqlite_master
-                WHERE type = 'table' AND name = ?1",
-            )
-            .unwrap();
-        let count = stmt.query_row(params![table_name], |row| row.get::<_, i64>(0))?;
-        Ok(count)
+    let subtasks = subtasks_of(conn, &[task_id as i64])?;
+    if subtasks.is_empty() {
+        return Ok(());
+    }
+    let placeholders = vec!["?"; subtasks.len()].join(", ");
+    let open_condition = format!(
+        "id IN ({}) AND COALESCE(task_state, 0) != {}",
+        placeholders,
+        TaskState::Ended.to_numeric()
+    );
+    if policy == SubtaskPolicy::Refuse {
+        let open: i64 = conn.query_row(
+            format!("SELECT COUNT(*) FROM tasks WHERE {}", open_condition).as_str(),
+            params_from_iter(subtasks.iter()),
+            |row| row.get(0),
+        )?;
+        if open > 0 {
+            return Err(TaskError::HasSubtasks {
+                task: task_number(conn, task_id)?,
+                subtasks: open as usize,
+                ending: true,
+            });
+        }
+        return Ok(());
     }
+    let mut values: Vec<rusqlite::types::Value> = vec![
+        (TaskState::Ended.to_numeric() as i64).into(),
+        modification.to_iso_string().into(),
+    ];
+    values.extend(subtasks.iter().map(|i| rusqlite::types::Value::from(*i)));
+    conn.execute(
+        format!(
+            "UPDATE tasks SET task_state = ?, modification = ? WHERE {}",
+            open_condition
+        )
+        .as_str(),
+        params_from_iter(values.iter()),
+    )?;
+    return Ok(());
 }
 
-#[cfg(test)]
-mod tests_manager {
+fn project_from_row(row: &Row) -> rusqlite::Result<Project> {
+    return Ok(Project {
+        name: row.get(0)?,
+        description: row.get(1)?,
+        priority: row.get(2)?,
+        archived: row.get(3)?,
+    });
+}
 
-    use super::*;
-    pub const DATABASE_PATH_TEST: &str = "./.todo_list_test/test.db";
-    pub const DATABASE_DIR_TEST_PATH: &str = "./.todo_list_test";
+fn project_id(conn: &Connection, name: &str) -> Result<i64, TaskError> {
+    return conn
+        .query_row("SELECT id FROM projects WHERE name = ?1", [name], |row| {
+            row.get(0)
+        })
+        .optional()?
+        .ok_or_else(|| TaskError::ProjectNotFound(String::from(name)));
+}
 
-    pub fn init_folder_test() {
-        fs::create_dir(DATABASE_DIR_TEST_PATH)
-            .expect("Was not possible to create the directory for de database");
-    }
+const NAME_POLICY_SETTING: &str = "name_policy";
+const COLUMNS_SETTING: &str = "columns";
+const SUBTASK_POLICY_SETTING: &str = "subtask_policy";
+
+fn read_setting(conn: &Connection, name: &str) -> rusqlite::Result<Option<String>> {
+    return conn
+        .query_row(
+            "SELECT value FROM settings WHERE name = ?1",
+            [name],
+            |row| row.get(0),
+        )
+        .optional();
+}
 
-    fn remove_test_files() {
-        fs::remove_file(DATABASE_PATH_TEST).expect("The file was not created in the write place");
-        fs::remove_dir(DATABASE_DIR_TEST_PATH)
-            .expect("The directory was not there, or not was capable to delete it");
-    }
+fn write_setting(conn: &Connection, name: &str, value: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO settings(name, value) VALUES (?1, ?2)
+            ON CONFLICT(name) DO UPDATE SET value = excluded.value",
+        [name, value],
+    )?;
+    return Ok(());
+}
 
-    #[test]
-    fn test_creation_database() {
-        init_folder_test();
-        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST);
-        conn.init_db();
-        let count = conn.check_table("tasks");
-        assert_eq!(count.unwrap(), 1, "The number of tables should be one");
-        remove_test_files();
-    }
+fn read_name_policy(conn: &Connection) -> Result<NamePolicy, TaskError> {
+    return match read_setting(conn, NAME_POLICY_SETTING)? {
+        Some(i) => NamePolicy::from_string(&i).ok_or(TaskError::InvalidSetting {
+            name: String::from(NAME_POLICY_SETTING),
+            value: Some(i),
+        }),
+        None => Ok(NamePolicy::default()),
+    };
+}
 
-    #[test]
-    fn test_insertion() {
-        init_folder_test();
-        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST);
-        conn.init_db();
+fn read_subtask_policy(conn: &Connection) -> Result<SubtaskPolicy, TaskError> {
+    return match read_setting(conn, SUBTASK_POLICY_SETTING)? {
+        Some(i) => SubtaskPolicy::from_string(&i).ok_or(TaskError::InvalidSetting {
+            name: String::from(SUBTASK_POLICY_SETTING),
+            value: Some(i),
+        }),
+        None => Ok(SubtaskPolicy::default()),
+    };
+}
 
-        let mut stmt = conn.conn.prepare("SELECT COUNT (*) FROM tasks ").unwrap();
-        let count_before = stmt
-            .query_row(params![], |row| row.get::<_, i64>(0))
-            .unwrap();
+fn read_columns(conn: &Connection) -> Result<Vec<ReportColumn>, TaskError> {
+    return match read_setting(conn, COLUMNS_SETTING)? {
+        Some(i) => report_column::parse_columns(&i).map_err(|_| TaskError::InvalidSetting {
+            name: String::from(COLUMNS_SETTING),
+            value: Some(i),
+        }),
+        None => Ok(DEFAULT_COLUMNS.to_vec()),
+    };
+}
 
-        assert_eq!(count_before, 0, "The number of insertions should be zero");
+// Runs inside the transaction that writes the name, so two processes cannot
+// both see the name as free.
+fn check_unique_name(conn: &Connection, name: &str, task_id: u64) -> Result<(), TaskError> {
+    if read_name_policy(conn)? != NamePolicy::Unique {
+        return Ok(());
+    }
+    let taken = conn
+        .query_row(
+            "SELECT 1 FROM tasks WHERE name = ?1 AND id != ?2",
+            params![name, task_id as i64],
+            |_| Ok(()),
+        )
+        .optional()?;
+    if taken.is_some() {
+        return Err(TaskError::DuplicateName(String::from(name)));
+    }
+    return Ok(());
+}
 
-        let task = ConcreteTaskBuilder::new().get_task();
+fn select_tasks_by_name(conn: &Connection, task_name: &str) -> rusqlite::Result<Vec<Task>> {
+    let mut select_tasks = conn.prepare(
+        format!(
+            "SELECT {} FROM tasks WHERE name = ?1 ORDER BY number",
+            select_columns()
+        )
+        .as_str(),
+    )?;
+    let tasks = select_tasks
+        .query_map([task_name], task_from_row)?
+        .collect::<rusqlite::Result<Vec<Task>>>()?;
+    return Ok(tasks);
+}
 
-        let _ = conn
-            .insert_task(&task)
-            .expect("The insertion should be okay");
+fn ambiguous_name(task_name: &str, tasks: &[Task]) -> TaskError {
+    return TaskError::AmbiguousName {
+        name: String::from(task_name),
+        candidates: tasks.iter().filter_map(|i| i.number).collect(),
+    };
+}
 
-        let mut stmt = conn.conn.prepare("SELECT COUNT (*) FROM tasks ").unwrap();
-        let count_after = stmt
-            .query_row(params![], |row| row.get::<_, i64>(0))
-            .unwrap();
+// A version 4 uuid, written like Taskwarrior does.
+fn random_uuid() -> String {
+    let mut bytes = rand::random::<[u8; 16]>();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = bytes
+        .iter()
+        .map(|i| format!("{:02x}", i))
+        .collect::<String>();
+    return format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    );
+}
 
-        assert_eq!(count_after, 1, "The number of insertions should be zero");
-        remove_test_files();
+fn update_in(conn: &Connection, task_id: u64, update: &TaskUpdate) -> Result<bool, TaskError> {
+    let task = conn
+        .query_row(
+            format!("SELECT {} FROM tasks WHERE id = ?1", select_columns()).as_str(),
+            [task_id as i64],
+            task_from_row,
+        )
+        .optional()?
+        .ok_or_else(|| TaskError::NotFound(format!("id {}", task_id)))?;
+
+    let mut new_task = update.apply(&task);
+    // The project may be named in another case than it was created with.
+    if let Some(i) = &new_task.project {
+        let name = conn
+            .query_row("SELECT name FROM projects WHERE name = ?1", [i], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        new_task.project = name.or_else(|| new_task.project.clone());
+    }
+    if new_task == task {
+        return Ok(false);
+    }
+    if new_task.name != task.name {
+        check_unique_name(conn, &new_task.name, task_id)?;
+    }
+    if update.modification.is_none() {
+        new_task.modification = Date::get_local_date();
     }
 
-    #[test]
-    fn test_get_id() {
-        init_folder_test();
-        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST);
-        conn.init_db();
-        let mut task = ConcreteTaskBuilder::new()
-            .set_description(String::from("Nice"))
-            .set_priority(Priority::High)
-            .set_name(String::from("Name Nie"))
-            .set_task_state(TaskState::Ended)
-            .set_term(Date::get_local_date())
-            .get_task();
-        task.id = 0;
+    conn.execute(
+        "UPDATE tasks
+            SET name = ?2, description = ?3, modification = ?4, term = ?5, task_state = ?6, priority = ?7
+            WHERE id = ?1",
+        params![
+            task_id as i64,
+            new_task.name,
+            new_task.description,
+            new_task.modification,
+            new_task.term,
+            new_task.task_state,
+            new_task.priority
+        ],
+    )?;
+    if new_task.tags != task.tags {
+        write_tags(conn, task_id, &new_task.tags)?;
+    }
+    if new_task.project != task.project {
+        write_project(conn, task_id, new_task.project.as_deref())?;
+    }
+    if new_task.parent != task.parent {
+        write_parent(conn, task_id, new_task.parent)?;
+    }
+    let ended = Some(TaskState::Ended);
+    if new_task.task_state == ended && task.task_state != ended {
+        end_subtasks(conn, task_id, &new_task.modification)?;
+    }
+    return Ok(true);
+}
+
+impl TaskRelationalManager for ConcreteTaskRelationalManager {
+    fn insert_task(&self, task: &Task) -> Result<u64, TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            check_unique_name(&tx, &task.name, task.id)?;
+            tx.execute(
+                "UPDATE counters SET value = value + 1 WHERE name = 'task_number'",
+                [],
+            )?;
+            let number: i64 = tx.query_row(
+                "SELECT value FROM counters WHERE name = 'task_number'",
+                [],
+                |row| row.get(0),
+            )?;
+            let parameters = params![
+                task.id as i64,
+                task.name,
+                task.description,
+                task.modification,
+                task.term,
+                task.task_state,
+                task.priority,
+                number,
+                task.uuid
+            ];
+
+            tx.execute(
+                format!(
+                    "INSERT INTO tasks ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    TASK_COLUMNS
+                )
+                .as_str(),
+                parameters,
+            )?;
+            write_tags(&tx, task.id, &task.tags)?;
+            if task.project.is_some() {
+                write_project(&tx, task.id, task.project.as_deref())?;
+            }
+            if task.parent.is_some() {
+                write_parent(&tx, task.id, task.parent)?;
+            }
+            tx.commit()?;
+
+            return Ok(number as u64);
+        });
+    }
+
+    fn query_tasks(&self, query: &TaskQuery) -> Result<Vec<Task>, TaskError> {
+        return self.retry(|| {
+            let (clauses, values) = query.to_sql();
+            let mut select_tasks = self
+                .conn
+                .prepare(format!("SELECT {} FROM tasks{}", select_columns(), clauses).as_str())?;
+            let tasks = select_tasks
+                .query_map(params_from_iter(values.iter()), task_from_row)?
+                .collect::<rusqlite::Result<Vec<Task>>>()?;
+            return Ok(tasks);
+        });
+    }
+
+    // bm25 gives lower scores to better matches, a word in the name weighs
+    // as much as ten in the description.
+    fn search_tasks(&self, search: &str, limit: u64) -> Result<Vec<SearchHit>, TaskError> {
+        let match_query = search::to_match_query(search)?;
+        let columns = TASK_COLUMNS
+            .split(", ")
+            .map(|i| format!("tasks.{}", i))
+            .chain([
+                String::from(TAGS_COLUMN),
+                String::from(PROJECT_COLUMN),
+                String::from("tasks.parent_id"),
+                blockers_column(),
+            ])
+            .collect::<Vec<String>>()
+            .join(", ");
+        return self.retry(|| {
+            let mut select_tasks = self.conn.prepare(
+                format!(
+                    "SELECT {}, snippet(tasks_search, -1, ?2, ?3, '...', 16)
+                        FROM tasks_search JOIN tasks ON tasks.id = tasks_search.rowid
+                        WHERE tasks_search MATCH ?1
+                        ORDER BY bm25(tasks_search, 10.0, 1.0), tasks.number
+                        LIMIT ?4",
+                    columns
+                )
+                .as_str(),
+            )?;
+            let hits = select_tasks
+                .query_map(
+                    params![
+                        match_query,
+                        HIGHLIGHT_START.to_string(),
+                        HIGHLIGHT_END.to_string(),
+                        limit.min(i64::MAX as u64) as i64
+                    ],
+                    |row| {
+                        Ok(SearchHit {
+                            task: task_from_row(row)?,
+                            snippet: row.get(13)?,
+                        })
+                    },
+                )?
+                .collect::<rusqlite::Result<Vec<SearchHit>>>()?;
+            return Ok(hits);
+        });
+    }
+
+    fn get_task_by_id(&self, task_id: u64) -> Result<Task, TaskError> {
+        return self.retry(|| {
+            let mut select_tasks = self.conn.prepare(
+                format!("SELECT {} FROM tasks WHERE id = ?1", select_columns()).as_str(),
+            )?;
+            let task = select_tasks
+                .query_row([task_id as i64], task_from_row)
+                .optional()?;
+            return task.ok_or_else(|| TaskError::NotFound(format!("id {}", task_id)));
+        });
+    }
+
+    fn get_task_by_number(&self, task_number: u64) -> Result<Task, TaskError> {
+        return self.retry(|| {
+            let mut select_tasks = self.conn.prepare(
+                format!("SELECT {} FROM tasks WHERE number = ?1", select_columns()).as_str(),
+            )?;
+            let task = select_tasks
+                .query_row([task_number as i64], task_from_row)
+                .optional()?;
+            return task.ok_or_else(|| TaskError::NotFound(format!("number {}", task_number)));
+        });
+    }
+
+    fn get_task_by_uuid(&self, uuid: &str) -> Result<Task, TaskError> {
+        return self.retry(|| {
+            let mut select_tasks = self.conn.prepare(
+                format!("SELECT {} FROM tasks WHERE uuid = ?1", select_columns()).as_str(),
+            )?;
+            let task = select_tasks.query_row([uuid], task_from_row).optional()?;
+            return task.ok_or_else(|| TaskError::NotFound(format!("uuid {}", uuid)));
+        });
+    }
+
+    fn get_task_by_name(&self, task_name: &str) -> Result<Task, TaskError> {
+        return self.retry(|| {
+            let tasks = select_tasks_by_name(&self.conn, task_name)?;
+            if tasks.len() > 1 {
+                return Err(ambiguous_name(task_name, &tasks));
+            }
+            return match tasks.into_iter().next() {
+                Some(i) => Ok(i),
+                None => Err(TaskError::NotFound(format!("name {:?}", task_name))),
+            };
+        });
+    }
+
+    fn get_tasks_by_name(&self, task_name: &str) -> Result<Vec<Task>, TaskError> {
+        return self.retry(|| {
+            let tasks = select_tasks_by_name(&self.conn, task_name)?;
+            if tasks.is_empty() {
+                return Err(TaskError::NotFound(format!("name {:?}", task_name)));
+            }
+            return Ok(tasks);
+        });
+    }
+
+    fn delete_task_by_name(&self, task_name: &str, all: bool) -> Result<usize, TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let tasks = select_tasks_by_name(&tx, task_name)?;
+            if tasks.is_empty() {
+                return Err(TaskError::NotFound(format!("name {:?}", task_name)));
+            }
+            if tasks.len() > 1 && !all {
+                return Err(ambiguous_name(task_name, &tasks));
+            }
+            let ids = tasks.iter().map(|i| i.id as i64).collect::<Vec<i64>>();
+            let deleted_tasks = delete_with_subtasks(&tx, &ids)?;
+            tx.commit()?;
+            return Ok(deleted_tasks);
+        });
+    }
+
+    fn delete_task_by_id(&self, task_id: u64) -> Result<(), TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            task_number(&tx, task_id)?;
+            delete_with_subtasks(&tx, &[task_id as i64])?;
+            tx.commit()?;
+            return Ok(());
+        });
+    }
+
+    // Only the conditions of the query are used, the sort and the limit
+    // would need a SQLite built with SQLITE_ENABLE_UPDATE_DELETE_LIMIT.
+    fn delete_tasks(&self, query: &TaskQuery) -> Result<usize, TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let mut values = Vec::new();
+            let clauses = query.where_sql(&mut values);
+            let ids = {
+                let mut select = tx.prepare(format!("SELECT id FROM tasks{}", clauses).as_str())?;
+                let ids = select
+                    .query_map(params_from_iter(values.iter()), |row| row.get::<_, i64>(0))?
+                    .collect::<rusqlite::Result<Vec<i64>>>()?;
+                ids
+            };
+            let deleted_tasks = delete_with_subtasks(&tx, &ids)?;
+            tx.commit()?;
+            return Ok(deleted_tasks);
+        });
+    }
+
+    fn update_task(&self, task_id: u64, update: &TaskUpdate) -> Result<bool, TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let changed = update_in(&tx, task_id, update)?;
+            tx.commit()?;
+            return Ok(changed);
+        });
+    }
+
+    fn update_tasks(&self, task_ids: &[u64], update: &TaskUpdate) -> Result<usize, TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let mut changed = 0;
+            for i in task_ids {
+                if update_in(&tx, *i, update)? {
+                    changed += 1;
+                }
+            }
+            tx.commit()?;
+            return Ok(changed);
+        });
+    }
+
+    fn get_name_policy(&self) -> Result<NamePolicy, TaskError> {
+        return self.retry(|| read_name_policy(&self.conn));
+    }
+
+    fn set_name_policy(&self, policy: &NamePolicy) -> Result<(), TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            if *policy == NamePolicy::Unique {
+                let repeated: Option<String> = tx
+                    .query_row(
+                        "SELECT name FROM tasks GROUP BY name HAVING COUNT(*) > 1 ORDER BY name LIMIT 1",
+                        [],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if let Some(i) = repeated {
+                    return Err(TaskError::DuplicateName(i));
+                }
+            }
+            write_setting(&tx, NAME_POLICY_SETTING, policy.to_str())?;
+            tx.commit()?;
+            return Ok(());
+        });
+    }
+
+    fn list_tags(&self) -> Result<Vec<(String, u64)>, TaskError> {
+        return self.retry(|| {
+            let mut select_tags = self.conn.prepare(
+                "SELECT tags.name, COUNT(*) FROM tags JOIN task_tags ON task_tags.tag_id = tags.id
+                    GROUP BY tags.id ORDER BY tags.name",
+            )?;
+            let tags = select_tags
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+                })?
+                .collect::<rusqlite::Result<Vec<(String, u64)>>>()?;
+            return Ok(tags);
+        });
+    }
+
+    fn get_columns(&self) -> Result<Vec<ReportColumn>, TaskError> {
+        return self.retry(|| read_columns(&self.conn));
+    }
+
+    fn get_subtask_policy(&self) -> Result<SubtaskPolicy, TaskError> {
+        return self.retry(|| read_subtask_policy(&self.conn));
+    }
+
+    fn set_subtask_policy(&self, policy: &SubtaskPolicy) -> Result<(), TaskError> {
+        return self.retry(|| {
+            write_setting(&self.conn, SUBTASK_POLICY_SETTING, policy.to_str())?;
+            return Ok(());
+        });
+    }
+
+    // Refused when the blocked task already blocks the blocker, directly or
+    // through other tasks. Returns false when the dependency was there.
+    fn add_dependency(&self, blocker_id: u64, blocked_id: u64) -> Result<bool, TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let blocker = task_number(&tx, blocker_id)?;
+            let blocked = task_number(&tx, blocked_id)?;
+            let cycle = tx
+                .query_row(
+                    "WITH RECURSIVE upstream(id) AS (
+                        SELECT ?1
+                        UNION SELECT task_dependencies.blocker_id FROM task_dependencies
+                            JOIN upstream ON task_dependencies.blocked_id = upstream.id
+                    )
+                    SELECT 1 FROM upstream WHERE id = ?2",
+                    params![blocker_id as i64, blocked_id as i64],
+                    |_| Ok(()),
+                )
+                .optional()?;
+            if cycle.is_some() {
+                return Err(TaskError::DependencyCycle { blocker, blocked });
+            }
+            let added = tx.execute(
+                "INSERT OR IGNORE INTO task_dependencies(blocker_id, blocked_id) VALUES (?1, ?2)",
+                params![blocker_id as i64, blocked_id as i64],
+            )?;
+            tx.commit()?;
+            return Ok(added > 0);
+        });
+    }
+
+    fn remove_dependency(&self, blocker_id: u64, blocked_id: u64) -> Result<bool, TaskError> {
+        return self.retry(|| {
+            let removed = self.conn.execute(
+                "DELETE FROM task_dependencies WHERE blocker_id = ?1 AND blocked_id = ?2",
+                params![blocker_id as i64, blocked_id as i64],
+            )?;
+            return Ok(removed > 0);
+        });
+    }
+
+    // Pairs of blocker and blocked ids, in the order of their numbers.
+    fn list_dependencies(&self) -> Result<Vec<(u64, u64)>, TaskError> {
+        return self.retry(|| {
+            let mut select = self.conn.prepare(
+                "SELECT blocker_id, blocked_id FROM task_dependencies
+                    JOIN tasks AS blockers ON blockers.id = blocker_id
+                    JOIN tasks AS blocked ON blocked.id = blocked_id
+                    ORDER BY blockers.number, blocked.number",
+            )?;
+            let dependencies = select
+                .query_map([], |row| {
+                    return Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64));
+                })?
+                .collect::<rusqlite::Result<Vec<(u64, u64)>>>()?;
+            return Ok(dependencies);
+        });
+    }
+
+    fn set_columns(&self, columns: &[ReportColumn]) -> Result<(), TaskError> {
+        return self.retry(|| {
+            write_setting(
+                &self.conn,
+                COLUMNS_SETTING,
+                &report_column::columns_to_string(columns),
+            )?;
+            return Ok(());
+        });
+    }
+
+    // Gives the tasks the numbers 1 to n keeping their order, the numbers
+    // are negated first so the unique index never sees two equal values.
+    fn compact_numbers(&self) -> Result<u64, TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let tasks = {
+                let mut select = tx.prepare("SELECT id, number FROM tasks ORDER BY number")?;
+                let tasks = select
+                    .query_map([], |row| {
+                        Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<(i64, Option<i64>)>>>()?;
+                tasks
+            };
+            tx.execute("UPDATE tasks SET number = -number", [])?;
+            let mut renumbered = 0;
+            for (i, (id, number)) in tasks.iter().enumerate() {
+                let new_number = i as i64 + 1;
+                tx.execute(
+                    "UPDATE tasks SET number = ?2 WHERE id = ?1",
+                    params![id, new_number],
+                )?;
+                if *number != Some(new_number) {
+                    renumbered += 1;
+                }
+            }
+            tx.execute(
+                "UPDATE counters SET value = ?1 WHERE name = 'task_number'",
+                [tasks.len() as i64],
+            )?;
+            tx.commit()?;
+            return Ok(renumbered);
+        });
+    }
+
+    // Gives a random uuid to the tasks that have none, so an export can be
+    // imported again without creating copies.
+    fn assign_uuids(&self) -> Result<u64, TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let ids = {
+                let mut select = tx.prepare("SELECT id FROM tasks WHERE uuid IS NULL")?;
+                let ids = select
+                    .query_map([], |row| row.get::<_, i64>(0))?
+                    .collect::<rusqlite::Result<Vec<i64>>>()?;
+                ids
+            };
+            for i in ids.iter() {
+                tx.execute(
+                    "UPDATE tasks SET uuid = ?2 WHERE id = ?1",
+                    params![i, random_uuid()],
+                )?;
+            }
+            tx.commit()?;
+            return Ok(ids.len() as u64);
+        });
+    }
+
+    fn create_project(&self, project: &Project) -> Result<(), TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            if project_id(&tx, &project.name).is_ok() {
+                return Err(TaskError::DuplicateProject(project.name.clone()));
+            }
+            tx.execute(
+                "INSERT INTO projects(name, description, priority, archived) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    project.name,
+                    project.description,
+                    project.priority,
+                    project.archived
+                ],
+            )?;
+            tx.commit()?;
+            return Ok(());
+        });
+    }
+
+    fn get_project(&self, name: &str) -> Result<Project, TaskError> {
+        return self.retry(|| {
+            return self
+                .conn
+                .query_row(
+                    "SELECT name, description, priority, archived FROM projects WHERE name = ?1",
+                    [name],
+                    project_from_row,
+                )
+                .optional()?
+                .ok_or_else(|| TaskError::ProjectNotFound(String::from(name)));
+        });
+    }
+
+    // Archived projects are only listed when archived is true.
+    fn list_projects(&self, archived: bool) -> Result<Vec<ProjectSummary>, TaskError> {
+        return self.retry(|| {
+            let mut select_projects = self.conn.prepare(
+                "SELECT projects.name, projects.description, projects.priority, projects.archived,
+                    COUNT(tasks.id), COUNT(CASE WHEN tasks.task_state = ?2 THEN 1 END)
+                    FROM projects LEFT JOIN tasks ON tasks.project_id = projects.id
+                    WHERE ?1 OR NOT projects.archived
+                    GROUP BY projects.id ORDER BY projects.name",
+            )?;
+            let projects = select_projects
+                .query_map(
+                    params![archived, TaskState::Ended.to_numeric() as i64],
+                    |row| {
+                        Ok(ProjectSummary {
+                            project: project_from_row(row)?,
+                            tasks: row.get::<_, i64>(4)? as u64,
+                            ended: row.get::<_, i64>(5)? as u64,
+                        })
+                    },
+                )?
+                .collect::<rusqlite::Result<Vec<ProjectSummary>>>()?;
+            return Ok(projects);
+        });
+    }
+
+    // A project can be renamed to the same name in another case.
+    fn rename_project(&self, name: &str, new_name: &str) -> Result<(), TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let id = project_id(&tx, name)?;
+            if project_id(&tx, new_name).is_ok_and(|i| i != id) {
+                return Err(TaskError::DuplicateProject(String::from(new_name)));
+            }
+            tx.execute(
+                "UPDATE projects SET name = ?2 WHERE id = ?1",
+                params![id, new_name],
+            )?;
+            tx.commit()?;
+            return Ok(());
+        });
+    }
+
+    fn archive_project(&self, name: &str, archived: bool) -> Result<(), TaskError> {
+        return self.retry(|| {
+            let changed = self.conn.execute(
+                "UPDATE projects SET archived = ?2 WHERE name = ?1",
+                params![name, archived],
+            )?;
+            if changed == 0 {
+                return Err(TaskError::ProjectNotFound(String::from(name)));
+            }
+            return Ok(());
+        });
+    }
+
+    // A project with tasks is only deleted when reassign says where they go,
+    // Some(None) leaves them without a project. Returns how many were moved.
+    fn delete_project(
+        &self,
+        name: &str,
+        reassign: Option<Option<&str>>,
+    ) -> Result<usize, TaskError> {
+        return self.retry(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let id = project_id(&tx, name)?;
+            let tasks: i64 = tx.query_row(
+                "SELECT COUNT(*) FROM tasks WHERE project_id = ?1",
+                [id],
+                |row| row.get(0),
+            )?;
+            let not_empty = || {
+                return TaskError::ProjectNotEmpty {
+                    name: String::from(name),
+                    tasks: tasks as usize,
+                };
+            };
+            let target = match reassign {
+                Some(Some(i)) => {
+                    let target = tx
+                        .query_row(
+                            "SELECT id, archived FROM projects WHERE name = ?1",
+                            [i],
+                            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?)),
+                        )
+                        .optional()?
+                        .ok_or_else(|| TaskError::ProjectNotFound(String::from(i)))?;
+                    if target.1 {
+                        return Err(TaskError::ArchivedProject(String::from(i)));
+                    }
+                    if target.0 == id && tasks > 0 {
+                        return Err(not_empty());
+                    }
+                    Some(target.0)
+                }
+                Some(None) => None,
+                None if tasks > 0 => return Err(not_empty()),
+                None => None,
+            };
+            let moved = tx.execute(
+                "UPDATE tasks SET project_id = ?2 WHERE project_id = ?1",
+                params![id, target],
+            )?;
+            tx.execute("DELETE FROM projects WHERE id = ?1", [id])?;
+            tx.commit()?;
+            return Ok(moved);
+        });
+    }
+}
+
+impl ConcreteTaskRelationalManager {
+    pub fn new<P: AsRef<Path>>(path: P, options: &ConnectionOptions) -> Result<Self, TaskError> {
+        let path = path.as_ref();
+        if !options.create && !path.exists() {
+            return Err(TaskError::DatabaseNotFound(path.display().to_string()));
+        }
+        let mut flags = OpenFlags::SQLITE_OPEN_READ_WRITE
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        if options.create {
+            flags |= OpenFlags::SQLITE_OPEN_CREATE;
+        }
+        let conn = Connection::open_with_flags(path, flags)?;
+        conn.busy_timeout(options.busy_timeout)?;
+        return Ok(Self {
+            conn,
+            options: options.clone(),
+        });
+    }
+
+    pub fn init_db(&self) -> Result<u32, TaskError> {
+        return self.migrate();
+    }
+
+    pub fn schema_version(&self) -> Result<u32, TaskError> {
+        return self.retry(|| Ok(migration::schema_version(&self.conn)?));
+    }
+
+    pub fn migrate(&self) -> Result<u32, TaskError> {
+        return self.retry(|| migration::migrate(&self.conn));
+    }
+
+    // SQLite waits up to the busy timeout by itself, but a deferred
+    // transaction that needs to upgrade its lock fails with SQLITE_BUSY at
+    // once, so those errors get a few more attempts. Every other error is
+    // permanent and returned immediately.
+    fn retry<T>(
+        &self,
+        mut operation: impl FnMut() -> Result<T, TaskError>,
+    ) -> Result<T, TaskError> {
+        let mut attempt = 0;
+        loop {
+            match operation() {
+                Err(err) if is_busy(&err) => {
+                    if attempt >= self.options.retries {
+                        return Err(TaskError::Busy(attempt + 1));
+                    }
+                    thread::sleep(self.options.backoff * 2u32.pow(attempt));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub fn check_table(&self, table_name: &str) -> Result<i64, TaskError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COUNT(*)
+                FROM sqlite_master
+                WHERE type = 'table' AND name = ?1",
+        )?;
+        let count = stmt.query_row(params![table_name], |row| row.get::<_, i64>(0))?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::let_unit_value, clippy::useless_vec)]
+mod tests_manager {
+
+    use super::*;
+    use crate::model::query::{Column, Comparison, Condition, SortKey};
+    pub const DATABASE_PATH_TEST: &str = "./.todo_list_test/test.db";
+    pub const DATABASE_DIR_TEST_PATH: &str = "./.todo_list_test";
+
+    pub fn init_folder_test() {
+        fs::create_dir(DATABASE_DIR_TEST_PATH)
+            .expect("Was not possible to create the directory for de database");
+    }
+
+    fn test_options() -> ConnectionOptions {
+        return ConnectionOptions {
+            create: true,
+            ..ConnectionOptions::default()
+        };
+    }
+
+    fn remove_test_files() {
+        fs::remove_file(DATABASE_PATH_TEST).expect("The file was not created in the write place");
+        fs::remove_dir(DATABASE_DIR_TEST_PATH)
+            .expect("The directory was not there, or not was capable to delete it");
+    }
+
+    #[test]
+    fn test_creation_database() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let count = conn.check_table("tasks");
+        assert_eq!(count.unwrap(), 1, "The number of tables should be one");
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_insertion() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+
+        let mut stmt = conn.conn.prepare("SELECT COUNT (*) FROM tasks ").unwrap();
+        let count_before = stmt
+            .query_row(params![], |row| row.get::<_, i64>(0))
+            .unwrap();
+
+        assert_eq!(count_before, 0, "The number of insertions should be zero");
+
+        let task = ConcreteTaskBuilder::new().get_task();
+
         let _ = conn
             .insert_task(&task)
             .expect("The insertion should be okay");
+
+        let mut stmt = conn.conn.prepare("SELECT COUNT (*) FROM tasks ").unwrap();
+        let count_after = stmt
+            .query_row(params![], |row| row.get::<_, i64>(0))
+            .unwrap();
+
+        assert_eq!(count_after, 1, "The number of insertions should be zero");
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_get_id() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let mut task = ConcreteTaskBuilder::new()
+            .set_description(String::from("Nice"))
+            .set_priority(Priority::High)
+            .set_name(String::from("Name Nie"))
+            .set_task_state(TaskState::Ended)
+            .set_term(Date::get_local_date())
+            .get_task();
+        task.id = 0;
+        task.number = Some(
+            conn.insert_task(&task)
+                .expect("The insertion should be okay"),
+        );
         let task_db = conn
             .get_task_by_id(0)
             .expect("Should exists a value with this id");
@@ -837,8 +2173,8 @@ mod tests_manager {
     #[test]
     fn test_get_name() {
         init_folder_test();
-        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST);
-        conn.init_db();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
         let name = String::from("Teste Name");
         let mut task = ConcreteTaskBuilder::new()
             .set_name(name.clone())
@@ -848,9 +2184,10 @@ mod tests_manager {
             .set_term(Date::get_local_date())
             .get_task();
         task.id = 0;
-        let _ = conn
-            .insert_task(&task)
-            .expect("The insertion should be okay");
+        task.number = Some(
+            conn.insert_task(&task)
+                .expect("The insertion should be okay"),
+        );
         let task_db = conn
             .get_task_by_name(&name)
             .expect("Should exists a value with this id");
@@ -859,20 +2196,298 @@ mod tests_manager {
         remove_test_files();
     }
 
+    #[test]
+    fn test_literal_none_description() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let mut task = ConcreteTaskBuilder::new()
+            .set_description(String::from("None"))
+            .get_task();
+        task.id = u64::MAX;
+        conn.insert_task(&task)
+            .expect("The insertion should be okay");
+
+        let task_db = conn
+            .get_task_by_id(u64::MAX)
+            .expect("Should exists a value with this id");
+        assert_eq!(
+            task_db.description,
+            Some(String::from("None")),
+            "A description written as \"None\" should be kept"
+        );
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_get_ambiguous_name() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let name = String::from("Twice");
+        let task_1 = ConcreteTaskBuilder::new().set_name(name.clone()).get_task();
+        let task_2 = ConcreteTaskBuilder::new().set_name(name.clone()).get_task();
+        let number_1 = conn.insert_task(&task_1).unwrap();
+        let number_2 = conn.insert_task(&task_2).unwrap();
+
+        match conn.get_task_by_name(&name) {
+            Err(TaskError::AmbiguousName { candidates, .. }) => {
+                assert_eq!(candidates.len(), 2, "Both tasks should be listed");
+                assert!(candidates.contains(&number_1));
+                assert!(candidates.contains(&number_2));
+            }
+            other => panic!("Expected an ambiguous name, got {:?}", other),
+        }
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_malformed_row_is_an_error() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        conn.conn
+            .execute(
+                "INSERT INTO tasks (id, name, modification) VALUES (5, 'Broken', 'yesterday')",
+                [],
+            )
+            .unwrap();
+
+        let result = conn.query_tasks(&TaskQuery::new());
+        assert!(
+            matches!(result, Err(TaskError::Storage(_))),
+            "A malformed row should be reported, not panic"
+        );
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_update_task() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let mut task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Before"))
+            .set_description(String::from("Text"))
+            .set_priority(Priority::Low)
+            .set_term(Date::get_local_date())
+            .get_task();
+        task.modification = Date {
+            day: 1,
+            month: 1,
+            year: 2000,
+        };
+        conn.insert_task(&task).unwrap();
+
+        let update = TaskUpdate::new()
+            .set_name(String::from("After"))
+            .set_priority(Some(Priority::Urgent))
+            .set_description(None)
+            .set_term(None);
+        let changed = conn
+            .update_task(task.id, &update)
+            .expect("The update should be okay");
+        assert!(changed, "The task should be reported as changed");
+
+        let task_db = conn.get_task_by_id(task.id).unwrap();
+        assert_eq!(task_db.name, String::from("After"));
+        assert_eq!(task_db.priority, Some(Priority::Urgent));
+        assert_eq!(
+            task_db.description, None,
+            "The description should be cleared"
+        );
+        assert_eq!(task_db.term, None, "The term should be cleared");
+        assert_eq!(task_db.task_state, None, "The state should be kept");
+        assert_eq!(task_db.modification, Date::get_local_date());
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_update_task_without_changes() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let mut task = ConcreteTaskBuilder::new()
+            .set_priority(Priority::High)
+            .get_task();
+        task.modification = Date {
+            day: 1,
+            month: 1,
+            year: 2000,
+        };
+        task.number = Some(conn.insert_task(&task).unwrap());
+
+        let update = TaskUpdate::new().set_priority(Some(Priority::High));
+        let changed = conn.update_task(task.id, &update).unwrap();
+
+        assert!(!changed, "Setting the same value should not be a change");
+        assert_eq!(
+            conn.get_task_by_id(task.id).unwrap(),
+            task,
+            "The modification date should be kept"
+        );
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_update_missing_task() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+
+        let result = conn.update_task(42, &TaskUpdate::new().set_name(String::from("a")));
+
+        assert!(matches!(result, Err(TaskError::NotFound(_))));
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_missing_database_is_reported() {
+        let result = ConcreteTaskRelationalManager::new(
+            "./.todo_list_missing/info.db3",
+            &ConnectionOptions::default(),
+        );
+        assert!(
+            matches!(result, Err(TaskError::DatabaseNotFound(_))),
+            "A missing database should not be created nor retried"
+        );
+    }
+
+    #[test]
+    fn test_locked_database_gives_up() {
+        init_folder_test();
+        let holder =
+            ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        holder.init_db().unwrap();
+        let options = ConnectionOptions {
+            busy_timeout: Duration::from_millis(0),
+            retries: 2,
+            backoff: Duration::from_millis(1),
+            create: false,
+        };
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &options).unwrap();
+
+        holder.conn.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        let result = conn.insert_task(&ConcreteTaskBuilder::new().get_task());
+        holder.conn.execute_batch("ROLLBACK").unwrap();
+
+        assert!(
+            matches!(result, Err(TaskError::Busy(3))),
+            "The insert should be tried three times and then reported"
+        );
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_locked_database_is_retried() {
+        init_folder_test();
+        let holder =
+            ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        holder.init_db().unwrap();
+        let options = ConnectionOptions {
+            busy_timeout: Duration::from_millis(0),
+            retries: 10,
+            backoff: Duration::from_millis(5),
+            create: false,
+        };
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &options).unwrap();
+
+        let (locked, wait_lock) = std::sync::mpsc::channel();
+        let handle = thread::spawn(move || {
+            holder.conn.execute_batch("BEGIN EXCLUSIVE").unwrap();
+            locked.send(()).unwrap();
+            thread::sleep(Duration::from_millis(50));
+            holder.conn.execute_batch("COMMIT").unwrap();
+        });
+        wait_lock.recv().unwrap();
+        let result = conn.insert_task(&ConcreteTaskBuilder::new().get_task());
+        handle.join().unwrap();
+
+        assert!(
+            result.is_ok(),
+            "The insert should succeed once the lock is released"
+        );
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_backup_database() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        conn.insert_task(&ConcreteTaskBuilder::new().get_task())
+            .unwrap();
+        drop(conn);
+
+        let backup = backup_database(Path::new(DATABASE_PATH_TEST)).unwrap();
+
+        assert!(
+            !Path::new(DATABASE_PATH_TEST).exists(),
+            "The old file should be moved"
+        );
+        let old =
+            ConcreteTaskRelationalManager::new(&backup, &ConnectionOptions::default()).unwrap();
+        assert_eq!(
+            old.query_tasks(&TaskQuery::new()).unwrap().len(),
+            1,
+            "The backup should keep the tasks"
+        );
+        drop(old);
+        fs::remove_file(backup).unwrap();
+        fs::remove_dir(DATABASE_DIR_TEST_PATH).unwrap();
+    }
+
+    #[test]
+    fn test_backups_in_the_same_second() {
+        init_folder_test();
+        let mut backups = Vec::new();
+        for i in ["one", "two"] {
+            let conn =
+                ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+            conn.init_db().unwrap();
+            conn.insert_task(
+                &ConcreteTaskBuilder::new()
+                    .set_name(String::from(i))
+                    .get_task(),
+            )
+            .unwrap();
+            drop(conn);
+            backups.push(backup_database(Path::new(DATABASE_PATH_TEST)).unwrap());
+        }
+
+        assert_ne!(
+            backups[0], backups[1],
+            "The second backup needs its own name"
+        );
+        for (backup, name) in backups.iter().zip(["one", "two"]) {
+            let old =
+                ConcreteTaskRelationalManager::new(backup, &ConnectionOptions::default()).unwrap();
+            let tasks = old.query_tasks(&TaskQuery::new()).unwrap();
+            assert_eq!(
+                tasks.iter().map(Task::get_name).collect::<Vec<String>>(),
+                vec![name]
+            );
+            drop(old);
+            fs::remove_file(backup).unwrap();
+        }
+        fs::remove_dir(DATABASE_DIR_TEST_PATH).unwrap();
+    }
+
     #[test]
     fn test_delete_by_id() {
         init_folder_test();
-        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST);
-        conn.init_db();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
         let task_id = 1;
 
         let mut task = ConcreteTaskBuilder::new().get_task();
 
         task.id = task_id;
 
-        let _ = conn
-            .insert_task(&task)
-            .expect("Should be possible to insert");
+        task.number = Some(
+            conn.insert_task(&task)
+                .expect("Should be possible to insert"),
+        );
 
         let result = conn
             .get_task_by_id(task_id)
@@ -885,10 +2500,10 @@ mod tests_manager {
             .expect("Should be possible to delete a value");
 
         let result = conn.get_task_by_id(task_id);
-        match result {
-            Some(_) => assert!(false, "Should not exist any element"),
-            None => {}
-        }
+        assert!(
+            matches!(result, Err(TaskError::NotFound(_))),
+            "Should not exist any element"
+        );
 
         remove_test_files();
     }
@@ -896,17 +2511,18 @@ mod tests_manager {
     #[test]
     fn test_delete_by_name() {
         init_folder_test();
-        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST);
-        conn.init_db();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
         let task_name = String::from("Task test");
 
-        let task = ConcreteTaskBuilder::new()
+        let mut task = ConcreteTaskBuilder::new()
             .set_name(task_name.clone())
             .get_task();
 
-        let _ = conn
-            .insert_task(&task)
-            .expect("Should be possible to insert");
+        task.number = Some(
+            conn.insert_task(&task)
+                .expect("Should be possible to insert"),
+        );
 
         let result = conn
             .get_task_by_name(&task_name)
@@ -915,38 +2531,38 @@ mod tests_manager {
         assert_eq!(result, task, "should be the same tasks");
 
         let _ = conn
-            .delete_task_by_name(&task_name)
+            .delete_task_by_name(&task_name, false)
             .expect("Should be possible to delete a value");
 
         let result = conn.get_task_by_name(&task_name);
-        match result {
-            Some(_) => assert!(false, "Should not exist any element"),
-            None => {}
-        }
+        assert!(
+            matches!(result, Err(TaskError::NotFound(_))),
+            "Should not exist any element"
+        );
 
         remove_test_files();
     }
 
     #[test]
-    fn test_get_tasks() {
+    fn test_query_all_tasks() {
         init_folder_test();
-        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST);
-        conn.init_db();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
 
-        let task_1 = ConcreteTaskBuilder::new()
+        let mut task_1 = ConcreteTaskBuilder::new()
             .set_name(String::from("Task 1"))
             .set_priority(Priority::High)
             .set_term(Date::get_local_date())
             .get_task();
 
-        let task_2 = ConcreteTaskBuilder::new()
+        let mut task_2 = ConcreteTaskBuilder::new()
             .set_name(String::from("Task 2"))
             .set_priority(Priority::High)
             .set_task_state(TaskState::Ended)
             .set_term(Date::get_local_date())
             .get_task();
 
-        let task_3 = ConcreteTaskBuilder::new()
+        let mut task_3 = ConcreteTaskBuilder::new()
             .set_name(String::from("Task 3"))
             .set_priority(Priority::Low)
             .set_task_state(TaskState::Pending)
@@ -954,14 +2570,14 @@ mod tests_manager {
             .set_term(Date::get_local_date())
             .get_task();
 
-        conn.insert_task(&task_1).unwrap();
-        conn.insert_task(&task_2).unwrap();
-        conn.insert_task(&task_3).unwrap();
+        task_1.number = Some(conn.insert_task(&task_1).unwrap());
+        task_2.number = Some(conn.insert_task(&task_2).unwrap());
+        task_3.number = Some(conn.insert_task(&task_3).unwrap());
 
         let mut tasks = vec![task_1, task_2, task_3];
         tasks.sort_by(|a, b| a.id.partial_cmp(&b.id).unwrap());
 
-        let mut db_tasks = conn.get_tasks().unwrap();
+        let mut db_tasks = conn.query_tasks(&TaskQuery::new()).unwrap();
         db_tasks.sort_by(|a, b| a.id.partial_cmp(&b.id).unwrap());
 
         assert_eq!(
@@ -976,4 +2592,694 @@ mod tests_manager {
 
         remove_test_files();
     }
+
+    #[test]
+    fn test_numbers_are_sequential() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+
+        let first = conn
+            .insert_task(&ConcreteTaskBuilder::new().get_task())
+            .unwrap();
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Second"))
+            .get_task();
+        let second = conn.insert_task(&task).unwrap();
+
+        assert_eq!((first, second), (1, 2), "Numbers should start at one");
+        assert_eq!(
+            conn.get_task_by_number(2).unwrap().get_id(),
+            task.get_id(),
+            "The number should find the task"
+        );
+        assert!(matches!(
+            conn.get_task_by_number(3),
+            Err(TaskError::NotFound(_))
+        ));
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_numbers_are_not_reused() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+
+        let task = ConcreteTaskBuilder::new().get_task();
+        conn.insert_task(&ConcreteTaskBuilder::new().get_task())
+            .unwrap();
+        conn.insert_task(&task).unwrap();
+        conn.delete_task_by_id(task.get_id()).unwrap();
+        let number = conn
+            .insert_task(&ConcreteTaskBuilder::new().get_task())
+            .unwrap();
+
+        assert_eq!(
+            number, 3,
+            "A deleted number should not point to another task"
+        );
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_compact_numbers() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+
+        let tasks: Vec<Task> = (0..4)
+            .map(|_| ConcreteTaskBuilder::new().get_task())
+            .collect();
+        for i in tasks.iter() {
+            conn.insert_task(i).unwrap();
+        }
+        conn.delete_task_by_id(tasks[0].get_id()).unwrap();
+        conn.delete_task_by_id(tasks[2].get_id()).unwrap();
+
+        let renumbered = conn.compact_numbers().expect("Compacting should be okay");
+
+        assert_eq!(renumbered, 2, "Both remaining tasks should move");
+        assert_eq!(conn.get_task_by_number(1).unwrap().id, tasks[1].id);
+        assert_eq!(conn.get_task_by_number(2).unwrap().id, tasks[3].id);
+        assert_eq!(
+            conn.insert_task(&ConcreteTaskBuilder::new().get_task())
+                .unwrap(),
+            3,
+            "The counter should follow the compacted numbers"
+        );
+        assert_eq!(conn.compact_numbers().unwrap(), 0);
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_uuids() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+
+        let uuid = "0a1b2c3d-0000-4000-8000-000000000001";
+        let task = ConcreteTaskBuilder::new()
+            .set_uuid(String::from(uuid))
+            .get_task();
+        let other = ConcreteTaskBuilder::new().get_task();
+        conn.insert_task(&task).unwrap();
+        conn.insert_task(&other).unwrap();
+
+        assert_eq!(conn.get_task_by_uuid(uuid).unwrap().id, task.id);
+        assert!(
+            conn.insert_task(
+                &ConcreteTaskBuilder::new()
+                    .set_uuid(String::from(uuid))
+                    .get_task()
+            )
+            .is_err(),
+            "A uuid belongs to one task"
+        );
+        assert_eq!(conn.assign_uuids().unwrap(), 1, "Only the task without one");
+        let assigned = conn.get_task_by_id(other.id).unwrap().uuid.unwrap();
+        assert_eq!(assigned.len(), 36);
+        assert_eq!(&assigned[14..15], "4");
+        assert_eq!(conn.get_task_by_uuid(&assigned).unwrap().id, other.id);
+        assert_eq!(conn.assign_uuids().unwrap(), 0);
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_tags() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Deploy"))
+            .set_tags(vec![
+                String::from("ui"),
+                String::from("api"),
+                String::from("ui"),
+            ])
+            .get_task();
+        let other = ConcreteTaskBuilder::new()
+            .set_tags(vec![String::from("api")])
+            .get_task();
+        conn.insert_task(&task).unwrap();
+        conn.insert_task(&other).unwrap();
+
+        assert_eq!(
+            conn.get_task_by_id(task.id).unwrap().tags,
+            vec!["api", "ui"],
+            "Tags are sorted and kept once"
+        );
+        let update = TaskUpdate::new()
+            .add_tag(String::from("docs"))
+            .remove_tag(String::from("ui"));
+        assert!(conn.update_task(task.id, &update).unwrap());
+        assert!(!conn.update_task(task.id, &update).unwrap());
+        assert_eq!(
+            conn.get_task_by_id(task.id).unwrap().tags,
+            vec!["api", "docs"]
+        );
+        assert_eq!(
+            conn.list_tags().unwrap(),
+            vec![(String::from("api"), 2), (String::from("docs"), 1)],
+            "ui has no task left"
+        );
+
+        let tagged = conn
+            .query_tasks(&TaskQuery::new().filter(Condition::HasTag(Some(String::from("docs")))))
+            .unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].id, task.id);
+        let hits = conn.search_tasks("deploy", 5).unwrap();
+        assert_eq!(hits[0].task.tags, vec!["api", "docs"]);
+
+        conn.delete_task_by_id(task.id).unwrap();
+        assert_eq!(conn.list_tags().unwrap(), vec![(String::from("api"), 1)]);
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_projects() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let backend = Project {
+            priority: Some(Priority::High),
+            ..Project::new(String::from("Backend"))
+        };
+        conn.create_project(&backend).unwrap();
+        conn.create_project(&Project::new(String::from("Web")))
+            .unwrap();
+        assert!(matches!(
+            conn.create_project(&Project::new(String::from("backend"))),
+            Err(TaskError::DuplicateProject(_))
+        ));
+        assert_eq!(conn.get_project("BACKEND").unwrap(), backend);
+
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Deploy"))
+            .set_project(String::from("Backend"))
+            .get_task();
+        let ended = ConcreteTaskBuilder::new()
+            .set_task_state(TaskState::Ended)
+            .set_project(String::from("Backend"))
+            .get_task();
+        conn.insert_task(&task).unwrap();
+        conn.insert_task(&ended).unwrap();
+        assert!(matches!(
+            conn.insert_task(
+                &ConcreteTaskBuilder::new()
+                    .set_project(String::from("Mobile"))
+                    .get_task()
+            ),
+            Err(TaskError::ProjectNotFound(_))
+        ));
+        assert_eq!(
+            conn.get_task_by_id(task.id).unwrap().project(),
+            Some("Backend")
+        );
+        let summaries = conn.list_projects(false).unwrap();
+        assert_eq!(
+            summaries
+                .iter()
+                .map(|i| (i.project.name.as_str(), i.tasks, i.ended))
+                .collect::<Vec<(&str, u64, u64)>>(),
+            vec![("Backend", 2, 1), ("Web", 0, 0)]
+        );
+
+        let update = TaskUpdate::new().set_project(Some(String::from("web")));
+        assert!(conn.update_task(task.id, &update).unwrap());
+        assert!(
+            !conn.update_task(task.id, &update).unwrap(),
+            "The case of the name is not a change"
+        );
+        let in_web = conn
+            .query_tasks(&TaskQuery::new().filter(Condition::InProject(Some(String::from("Web")))))
+            .unwrap();
+        assert_eq!(in_web.len(), 1);
+        assert_eq!(in_web[0].project(), Some("Web"));
+
+        conn.archive_project("Web", true).unwrap();
+        assert_eq!(conn.list_projects(false).unwrap().len(), 1);
+        assert_eq!(conn.list_projects(true).unwrap().len(), 2);
+        assert!(matches!(
+            conn.update_task(ended.id, &update),
+            Err(TaskError::ArchivedProject(_))
+        ));
+        conn.archive_project("Web", false).unwrap();
+
+        conn.rename_project("Web", "Frontend").unwrap();
+        assert!(matches!(
+            conn.rename_project("Frontend", "backend"),
+            Err(TaskError::DuplicateProject(_))
+        ));
+        assert_eq!(
+            conn.get_task_by_id(task.id).unwrap().project(),
+            Some("Frontend")
+        );
+
+        assert!(matches!(
+            conn.delete_project("Frontend", None),
+            Err(TaskError::ProjectNotEmpty { tasks: 1, .. })
+        ));
+        assert_eq!(
+            conn.delete_project("Frontend", Some(Some("Backend")))
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            conn.get_task_by_id(task.id).unwrap().project(),
+            Some("Backend")
+        );
+        assert_eq!(conn.delete_project("Backend", Some(None)).unwrap(), 2);
+        assert_eq!(conn.get_task_by_id(task.id).unwrap().project(), None);
+        assert!(conn.list_projects(true).unwrap().is_empty());
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_subtasks() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let release = ConcreteTaskBuilder::new().get_task();
+        let build = ConcreteTaskBuilder::new().set_parent(release.id).get_task();
+        let unit = ConcreteTaskBuilder::new().set_parent(build.id).get_task();
+        for i in [&release, &build, &unit] {
+            conn.insert_task(i).unwrap();
+        }
+        assert_eq!(
+            conn.get_task_by_id(unit.id).unwrap().parent(),
+            Some(build.id)
+        );
+        assert!(matches!(
+            conn.update_task(release.id, &TaskUpdate::new().set_parent(Some(unit.id))),
+            Err(TaskError::SubtaskCycle { task, parent }) if task == "1" && parent == "3"
+        ));
+        assert!(matches!(
+            conn.update_task(build.id, &TaskUpdate::new().set_parent(Some(build.id))),
+            Err(TaskError::SubtaskCycle { .. })
+        ));
+        assert!(matches!(
+            conn.insert_task(&ConcreteTaskBuilder::new().set_parent(u64::MAX).get_task()),
+            Err(TaskError::NotFound(_))
+        ));
+
+        let ended = TaskUpdate::new().set_task_state(Some(TaskState::Ended));
+        conn.set_subtask_policy(&SubtaskPolicy::Refuse).unwrap();
+        assert!(matches!(
+            conn.update_task(release.id, &ended),
+            Err(TaskError::HasSubtasks {
+                subtasks: 2,
+                ending: true,
+                ..
+            })
+        ));
+        assert!(matches!(
+            conn.delete_task_by_id(build.id),
+            Err(TaskError::HasSubtasks {
+                subtasks: 1,
+                ending: false,
+                ..
+            })
+        ));
+        conn.set_subtask_policy(&SubtaskPolicy::Cascade).unwrap();
+        conn.update_task(build.id, &ended).unwrap();
+        assert_eq!(
+            conn.get_task_by_id(unit.id).unwrap().task_state(),
+            Some(&TaskState::Ended)
+        );
+        conn.set_subtask_policy(&SubtaskPolicy::Keep).unwrap();
+        conn.delete_task_by_id(release.id).unwrap();
+        assert_eq!(conn.get_task_by_id(build.id).unwrap().parent(), None);
+        conn.set_subtask_policy(&SubtaskPolicy::Cascade).unwrap();
+        conn.delete_task_by_id(build.id).unwrap();
+        assert!(conn.query_tasks(&TaskQuery::new()).unwrap().is_empty());
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_dependencies() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let build = ConcreteTaskBuilder::new().get_task();
+        let test = ConcreteTaskBuilder::new().get_task();
+        let deploy = ConcreteTaskBuilder::new()
+            .set_task_state(TaskState::Pending)
+            .get_task();
+        for i in [&build, &test, &deploy] {
+            conn.insert_task(i).unwrap();
+        }
+        assert!(conn.add_dependency(build.id, test.id).unwrap());
+        assert!(conn.add_dependency(test.id, deploy.id).unwrap());
+        assert!(!conn.add_dependency(test.id, deploy.id).unwrap());
+        assert!(matches!(
+            conn.add_dependency(deploy.id, build.id),
+            Err(TaskError::DependencyCycle { blocker, blocked }) if blocker == "3" && blocked == "1"
+        ));
+        assert!(matches!(
+            conn.add_dependency(build.id, build.id),
+            Err(TaskError::DependencyCycle { .. })
+        ));
+        assert_eq!(
+            conn.list_dependencies().unwrap(),
+            vec![(build.id, test.id), (test.id, deploy.id)]
+        );
+
+        let deploy_row = conn.get_task_by_id(deploy.id).unwrap();
+        assert_eq!(deploy_row.blockers(), &[test.id]);
+        assert_eq!(deploy_row.to_vec()[5], "pending (blocked)");
+        let blocked = conn
+            .query_tasks(&TaskQuery::new().filter(Condition::Blocked))
+            .unwrap();
+        assert_eq!(blocked.len(), 2);
+
+        let ended = TaskUpdate::new().set_task_state(Some(TaskState::Ended));
+        conn.update_task(deploy.id, &ended).unwrap();
+        let deploy_row = conn.get_task_by_id(deploy.id).unwrap();
+        assert!(
+            !deploy_row.is_blocked(),
+            "An ended task no longer waits on its blockers"
+        );
+        assert_eq!(deploy_row.to_vec()[5], "ended");
+        let blocked = conn
+            .query_tasks(&TaskQuery::new().filter(Condition::Blocked))
+            .unwrap();
+        assert_eq!(blocked.len(), 1);
+        conn.update_task(test.id, &ended).unwrap();
+        assert!(!conn.get_task_by_id(deploy.id).unwrap().is_blocked());
+        assert!(conn.remove_dependency(build.id, test.id).unwrap());
+        assert!(!conn.remove_dependency(build.id, test.id).unwrap());
+        conn.delete_task_by_id(test.id).unwrap();
+        assert!(conn.list_dependencies().unwrap().is_empty());
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_unique_name_policy() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        assert_eq!(conn.get_name_policy().unwrap(), NamePolicy::Duplicates);
+
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Deploy"))
+            .get_task();
+        let other = ConcreteTaskBuilder::new()
+            .set_name(String::from("Review"))
+            .get_task();
+        conn.insert_task(&task).unwrap();
+        conn.insert_task(&other).unwrap();
+        conn.set_name_policy(&NamePolicy::Unique).unwrap();
+
+        let duplicate = ConcreteTaskBuilder::new()
+            .set_name(String::from("Deploy"))
+            .get_task();
+        assert!(
+            matches!(
+                conn.insert_task(&duplicate),
+                Err(TaskError::DuplicateName(_))
+            ),
+            "A repeated name should be refused"
+        );
+        let rename = TaskUpdate::new().set_name(String::from("Deploy"));
+        assert!(
+            matches!(
+                conn.update_task(other.id, &rename),
+                Err(TaskError::DuplicateName(_))
+            ),
+            "Renaming to a taken name should be refused"
+        );
+        let keep = TaskUpdate::new()
+            .set_name(String::from("Deploy"))
+            .set_priority(Some(Priority::High));
+        assert!(
+            conn.update_task(task.id, &keep).unwrap(),
+            "A task keeps its own name"
+        );
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_columns_setting() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        assert_eq!(conn.get_columns().unwrap(), DEFAULT_COLUMNS.to_vec());
+
+        let columns = vec![ReportColumn::Name, ReportColumn::DaysLeft];
+        conn.set_columns(&columns).unwrap();
+        assert_eq!(conn.get_columns().unwrap(), columns);
+
+        write_setting(&conn.conn, COLUMNS_SETTING, "name,due").unwrap();
+        assert!(matches!(
+            conn.get_columns(),
+            Err(TaskError::InvalidSetting { value: Some(i), .. }) if i == "name,due"
+        ));
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_unique_policy_needs_unique_names() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        for _ in 0..2 {
+            conn.insert_task(
+                &ConcreteTaskBuilder::new()
+                    .set_name(String::from("Deploy"))
+                    .get_task(),
+            )
+            .unwrap();
+        }
+
+        let result = conn.set_name_policy(&NamePolicy::Unique);
+
+        assert!(matches!(result, Err(TaskError::DuplicateName(i)) if i == "Deploy"));
+        assert_eq!(conn.get_name_policy().unwrap(), NamePolicy::Duplicates);
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_delete_repeated_name_needs_all() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        for _ in 0..2 {
+            conn.insert_task(
+                &ConcreteTaskBuilder::new()
+                    .set_name(String::from("Deploy"))
+                    .get_task(),
+            )
+            .unwrap();
+        }
+
+        let result = conn.delete_task_by_name("Deploy", false);
+        assert!(
+            matches!(result, Err(TaskError::AmbiguousName { candidates, .. }) if candidates == vec![1, 2]),
+            "The candidates should be listed by number"
+        );
+        assert_eq!(
+            conn.query_tasks(&TaskQuery::new()).unwrap().len(),
+            2,
+            "Nothing is deleted"
+        );
+
+        assert_eq!(conn.delete_task_by_name("Deploy", true).unwrap(), 2);
+        assert!(conn.query_tasks(&TaskQuery::new()).unwrap().is_empty());
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_update_tasks() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let task_1 = ConcreteTaskBuilder::new()
+            .set_priority(Priority::High)
+            .get_task();
+        let task_2 = ConcreteTaskBuilder::new().get_task();
+        conn.insert_task(&task_1).unwrap();
+        conn.insert_task(&task_2).unwrap();
+
+        let update = TaskUpdate::new().set_priority(Some(Priority::High));
+        let changed = conn.update_tasks(&[task_1.id, task_2.id], &update).unwrap();
+
+        assert_eq!(changed, 1, "Only the second task had another priority");
+        assert_eq!(
+            conn.get_task_by_id(task_2.id).unwrap().priority,
+            Some(Priority::High)
+        );
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_query_tasks() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let date = |i: &str| Date::from_string(String::from(i)).unwrap().unwrap();
+        let tasks = [
+            ConcreteTaskBuilder::new()
+                .set_name(String::from("Write report"))
+                .set_priority(Priority::Urgent)
+                .set_term(date("10-1-2025"))
+                .get_task(),
+            ConcreteTaskBuilder::new()
+                .set_name(String::from("Review"))
+                .set_description(String::from("Read the REPORT draft"))
+                .set_priority(Priority::High)
+                .set_term(date("5-1-2025"))
+                .get_task(),
+            ConcreteTaskBuilder::new()
+                .set_name(String::from("Lunch"))
+                .set_priority(Priority::Low)
+                .get_task(),
+            ConcreteTaskBuilder::new()
+                .set_name(String::from("Plan"))
+                .get_task(),
+        ];
+        for i in tasks.iter() {
+            conn.insert_task(i).unwrap();
+        }
+        let names = |query: TaskQuery| {
+            conn.query_tasks(&query)
+                .unwrap()
+                .iter()
+                .map(|i| i.get_name())
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(
+            names(TaskQuery::new().filter(Condition::priority(
+                Comparison::GreaterEqual,
+                &Priority::High
+            ))),
+            vec!["Write report", "Review"]
+        );
+        assert_eq!(
+            names(
+                TaskQuery::new().filter(Condition::priority(Comparison::NotEqual, &Priority::Low))
+            ),
+            vec!["Write report", "Review", "Plan"],
+            "Tasks without priority are not low"
+        );
+        assert_eq!(
+            names(TaskQuery::new().filter(Condition::term(Comparison::Less, &date("6-1-2025")))),
+            vec!["Review"]
+        );
+        assert_eq!(
+            names(TaskQuery::new().filter(Condition::Search(String::from("report")))),
+            vec!["Write report", "Review"],
+            "The search should look at the description and ignore case"
+        );
+        assert_eq!(
+            names(
+                TaskQuery::new()
+                    .sort_by(SortKey {
+                        column: Column::Term,
+                        descending: false,
+                    })
+                    .limit(3)
+            ),
+            vec!["Review", "Write report", "Lunch"],
+            "Tasks without term should come last"
+        );
+        assert_eq!(
+            names(TaskQuery::new().filter(Condition::IsNull {
+                column: Column::Priority,
+                negated: false,
+            })),
+            vec!["Plan"]
+        );
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_delete_tasks() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let keep = ConcreteTaskBuilder::new()
+            .set_task_state(TaskState::Pending)
+            .get_task();
+        conn.insert_task(&keep).unwrap();
+        for _ in 0..2 {
+            conn.insert_task(
+                &ConcreteTaskBuilder::new()
+                    .set_task_state(TaskState::Ended)
+                    .get_task(),
+            )
+            .unwrap();
+        }
+
+        let query =
+            TaskQuery::new().filter(Condition::task_state(Comparison::Equal, &TaskState::Ended));
+        let deleted = conn.delete_tasks(&query).unwrap();
+
+        assert_eq!(deleted, 2);
+        let left = conn.query_tasks(&TaskQuery::new()).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].id, keep.id, "Only the ended tasks are deleted");
+        remove_test_files();
+    }
+
+    #[test]
+    fn test_search_tasks() {
+        init_folder_test();
+        let conn = ConcreteTaskRelationalManager::new(DATABASE_PATH_TEST, &test_options()).unwrap();
+        conn.init_db().unwrap();
+        let described = ConcreteTaskBuilder::new()
+            .set_name(String::from("Ops"))
+            .set_description(String::from(
+                "The TLS certificate of api.example.com expires in March",
+            ))
+            .get_task();
+        let named = ConcreteTaskBuilder::new()
+            .set_name(String::from("Renew certificate"))
+            .get_task();
+        let other = ConcreteTaskBuilder::new()
+            .set_name(String::from("Lunch"))
+            .get_task();
+        for i in [&described, &named, &other] {
+            conn.insert_task(i).unwrap();
+        }
+        let ids = |search: &str| {
+            conn.search_tasks(search, 10)
+                .unwrap()
+                .iter()
+                .map(|i| i.task.id)
+                .collect::<Vec<u64>>()
+        };
+
+        assert_eq!(
+            ids("certif*"),
+            vec![named.id, described.id],
+            "A match in the name should rank first"
+        );
+        assert_eq!(ids("\"tls certificate\""), vec![described.id]);
+        assert_eq!(ids("api.example.com"), vec![described.id]);
+        let hit = &conn.search_tasks("march", 10).unwrap()[0];
+        assert!(
+            hit.snippet.contains("\u{2}March\u{3}"),
+            "The match should be highlighted in {:?}",
+            hit.snippet
+        );
+
+        conn.update_task(
+            other.id,
+            &TaskUpdate::new().set_description(Some(String::from("Buy a certificate frame"))),
+        )
+        .unwrap();
+        conn.delete_task_by_id(named.id).unwrap();
+        let mut found = ids("certificate");
+        found.sort();
+        let mut expected = vec![described.id, other.id];
+        expected.sort();
+        assert_eq!(
+            found, expected,
+            "The index should follow updates and deletions"
+        );
+        remove_test_files();
+    }
 }
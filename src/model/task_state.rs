@@ -1,3 +1,7 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Serialize, Serializer};
+use std::fmt::Display;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TaskState {
     Pending,
@@ -13,19 +17,60 @@ impl TaskState {
             Self::Ended => "ended",
         };
     }
-    pub fn to_string(&self) -> String {
-        return String::from(self.to_str());
-    }
-
-    pub fn from_string(state: &String) -> Option<TaskState> {
-        return match state.as_str() {
+    pub fn from_string(state: &str) -> Option<TaskState> {
+        return match state {
             "pending" => Some(Self::Pending),
             "in progress" => Some(Self::InProgress),
             "ended" => Some(Self::Ended),
             _ => None,
         };
     }
+
+    pub fn to_numeric(&self) -> usize {
+        return match self {
+            Self::Pending => 1,
+            Self::InProgress => 2,
+            Self::Ended => 3,
+        };
+    }
+
+    pub fn from_numeric(state: usize) -> Option<TaskState> {
+        return match state {
+            1 => Some(Self::Pending),
+            2 => Some(Self::InProgress),
+            3 => Some(Self::Ended),
+            _ => None,
+        };
+    }
+}
+
+impl Serialize for TaskState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_str(self.to_str());
+    }
+}
+
+impl ToSql for TaskState {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        return Ok(ToSqlOutput::from(self.to_numeric() as i64));
+    }
+}
+
+impl FromSql for TaskState {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let numeric = value.as_i64()?;
+        return usize::try_from(numeric)
+            .ok()
+            .and_then(TaskState::from_numeric)
+            .ok_or(FromSqlError::OutOfRange(numeric));
+    }
 }
+impl Display for TaskState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
 #[cfg(test)]
 mod tests_task_state {
     use super::*;
@@ -49,6 +94,14 @@ mod tests_task_state {
         );
     }
 
+    #[test]
+    fn test_numeric() {
+        for i in [TaskState::Pending, TaskState::InProgress, TaskState::Ended] {
+            assert_eq!(TaskState::from_numeric(i.to_numeric()), Some(i));
+        }
+        assert_eq!(TaskState::from_numeric(0), None, "0 is not a task state");
+    }
+
     #[test]
     fn test_to_string() {
         assert_eq!(
@@ -0,0 +1,410 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+use crate::model::delimited::{ImportedRow, RowError};
+use crate::model::error::TaskError;
+use crate::model::project;
+use crate::model::tag;
+use crate::{ConcreteTaskBuilder, Date, Priority, Task, TaskBuilder, TaskState};
+
+// Fields read into a task, and the ones Taskwarrior computes when exporting,
+// anything else has no equivalent here and is reported.
+const KNOWN_FIELDS: [&str; 16] = [
+    "uuid",
+    "status",
+    "description",
+    "priority",
+    "due",
+    "entry",
+    "modified",
+    "start",
+    "end",
+    "annotations",
+    "tags",
+    "project",
+    "id",
+    "urgency",
+    STATE_FIELD,
+    PRIORITY_FIELD,
+];
+
+// Taskwarrior keeps fields it does not know, so the state and priority that
+// its own fields cannot tell apart travel in these two.
+const STATE_FIELD: &str = "todo_list_state";
+const PRIORITY_FIELD: &str = "todo_list_priority";
+const NO_STATE: &str = "none";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct TaskwarriorImport {
+    pub rows: Vec<ImportedRow>,
+    pub errors: Vec<RowError>,
+    pub ignored_fields: BTreeMap<String, usize>,
+    pub deleted: usize,
+}
+
+#[derive(Serialize)]
+struct Annotation {
+    entry: String,
+    description: String,
+}
+
+// In Taskwarrior the description is the title of the task, the description
+// of a task here becomes its single annotation.
+#[derive(Serialize)]
+struct ExportedTask {
+    uuid: String,
+    status: &'static str,
+    description: String,
+    entry: String,
+    modified: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+    todo_list_state: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    todo_list_priority: Option<String>,
+}
+
+fn priority_code(priority: &Priority) -> &'static str {
+    return match priority {
+        Priority::Urgent | Priority::High => "H",
+        Priority::Normal => "M",
+        Priority::Low => "L",
+    };
+}
+
+// Taskwarrior has no state between pending and completed, a started task is
+// the closest to one in progress.
+fn to_exported(task: &Task, uuid: String) -> ExportedTask {
+    let modified = task.modification().to_utc_timestamp();
+    let state = task.task_state();
+    return ExportedTask {
+        uuid,
+        status: if state == Some(&TaskState::Ended) {
+            "completed"
+        } else {
+            "pending"
+        },
+        description: task.get_name(),
+        entry: modified.clone(),
+        start: (state == Some(&TaskState::InProgress)).then(|| modified.clone()),
+        end: (state == Some(&TaskState::Ended)).then(|| modified.clone()),
+        due: task.term().map(|i| i.to_utc_timestamp()),
+        priority: task.priority().map(priority_code),
+        project: task.project().map(String::from),
+        tags: task.tags().to_vec(),
+        annotations: task
+            .description()
+            .map(|i| Annotation {
+                entry: modified.clone(),
+                description: String::from(i),
+            })
+            .into_iter()
+            .collect(),
+        todo_list_state: String::from(state.map(|i| i.to_str()).unwrap_or(NO_STATE)),
+        todo_list_priority: task.priority().map(|i| String::from(i.to_str())),
+        modified,
+    };
+}
+
+// Tasks without a uuid cannot be exported, assign_uuids gives them one.
+pub fn write_tasks<W: Write>(out: &mut W, tasks: &[Task]) -> Result<(), TaskError> {
+    let exported = tasks
+        .iter()
+        .filter_map(|i| Some(to_exported(i, String::from(i.uuid()?))))
+        .collect::<Vec<ExportedTask>>();
+    serde_json::to_writer_pretty(&mut *out, &exported).map_err(io::Error::from)?;
+    writeln!(out)?;
+    return Ok(());
+}
+
+fn text<'a>(object: &'a serde_json::Map<String, Value>, field: &str) -> Option<&'a str> {
+    return object.get(field).and_then(|i| i.as_str());
+}
+
+fn date_field(
+    object: &serde_json::Map<String, Value>,
+    field: &str,
+) -> Result<Option<Date>, String> {
+    return match text(object, field) {
+        Some(i) => Date::from_utc_timestamp(i)
+            .map(Some)
+            .ok_or_else(|| format!("invalid {} date {:?}", field, i)),
+        None => Ok(None),
+    };
+}
+
+fn from_object(object: &serde_json::Map<String, Value>) -> Result<Task, String> {
+    let uuid = text(object, "uuid").ok_or("the task has no uuid")?;
+    let description = text(object, "description")
+        .map(str::trim)
+        .filter(|i| !i.is_empty())
+        .ok_or("the task has no description")?;
+    let mut builder = ConcreteTaskBuilder::new()
+        .set_uuid(String::from(uuid))
+        .set_name(String::from(description));
+
+    let state = match (text(object, "status"), object.contains_key("start")) {
+        (Some("completed"), _) => TaskState::Ended,
+        (Some("pending" | "waiting" | "recurring"), true) => TaskState::InProgress,
+        (Some("pending" | "waiting" | "recurring"), false) => TaskState::Pending,
+        (status, _) => return Err(format!("unknown status {:?}", status.unwrap_or(""))),
+    };
+    // A task without state is exported as pending, the field only counts
+    // while the task was not started or completed in Taskwarrior.
+    if text(object, STATE_FIELD) != Some(NO_STATE) || state != TaskState::Pending {
+        builder = builder.set_task_state(state);
+    }
+    let priority = match text(object, "priority") {
+        Some("H") => Some(Priority::High),
+        Some("M") => Some(Priority::Normal),
+        Some("L") => Some(Priority::Low),
+        Some(i) => return Err(format!("unknown priority {:?}, expected H, M or L", i)),
+        None => None,
+    };
+    // The exact priority is kept unless it was changed in Taskwarrior.
+    let exact = text(object, PRIORITY_FIELD).and_then(Priority::from_string);
+    let priority = match (priority, exact) {
+        (Some(i), Some(j)) if priority_code(&i) == priority_code(&j) => Some(j),
+        (i, _) => i,
+    };
+    if let Some(i) = priority {
+        builder = builder.set_priority(i);
+    }
+    if let Some(i) = date_field(object, "due")? {
+        builder = builder.set_term(i);
+    }
+    let modification = match date_field(object, "modified")? {
+        Some(i) => Some(i),
+        None => date_field(object, "entry")?,
+    };
+    if let Some(i) = modification {
+        builder = builder.set_modification(i);
+    }
+
+    let tags = object
+        .get("tags")
+        .and_then(|i| i.as_array())
+        .map(|i| {
+            i.iter()
+                .filter_map(|j| j.as_str())
+                .map(|j| tag::parse_tag(j).map_err(|err| err.to_string()))
+                .collect::<Result<Vec<String>, String>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+    builder = builder.set_tags(tags);
+    if let Some(i) = text(object, "project") {
+        builder =
+            builder.set_project(project::parse_project_name(i).map_err(|err| err.to_string())?);
+    }
+
+    let annotations = object
+        .get("annotations")
+        .and_then(|i| i.as_array())
+        .map(|i| {
+            i.iter()
+                .filter_map(|j| j.get("description")?.as_str())
+                .collect::<Vec<&str>>()
+        })
+        .unwrap_or_default();
+    if !annotations.is_empty() {
+        builder = builder.set_description(annotations.join("\n"));
+    }
+    return Ok(builder.get_task());
+}
+
+// Reads the array written by "task export", or the one task per line of
+// the older versions. Rows are the positions of the tasks, from 1.
+pub fn read_tasks<R: Read>(mut reader: R) -> Result<TaskwarriorImport, TaskError> {
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    let items: Vec<Result<Value, String>> = if content.trim_start().starts_with('[') {
+        match serde_json::from_str::<Vec<Value>>(&content) {
+            Ok(i) => i.into_iter().map(Ok).collect(),
+            Err(err) => return Err(TaskError::InvalidImport(err.to_string())),
+        }
+    } else {
+        content
+            .lines()
+            .filter(|i| !i.trim().is_empty())
+            .map(|i| serde_json::from_str::<Value>(i).map_err(|err| err.to_string()))
+            .collect()
+    };
+
+    let mut import = TaskwarriorImport::default();
+    for (i, item) in items.into_iter().enumerate() {
+        let row = i + 1;
+        let object = match item {
+            Ok(Value::Object(j)) => j,
+            Ok(_) => {
+                import.errors.push(RowError {
+                    row,
+                    message: String::from("expected a task object"),
+                });
+                continue;
+            }
+            Err(message) => {
+                import.errors.push(RowError { row, message });
+                continue;
+            }
+        };
+        if text(&object, "status") == Some("deleted") {
+            import.deleted += 1;
+            continue;
+        }
+        match from_object(&object) {
+            Ok(task) => {
+                for j in object
+                    .keys()
+                    .filter(|j| !KNOWN_FIELDS.contains(&j.as_str()))
+                {
+                    *import.ignored_fields.entry(j.clone()).or_insert(0) += 1;
+                }
+                import.rows.push(ImportedRow { row, task });
+            }
+            Err(message) => import.errors.push(RowError { row, message }),
+        }
+    }
+    return Ok(import);
+}
+
+#[cfg(test)]
+mod tests_taskwarrior {
+    use super::*;
+
+    const EXPORT: &str = r#"[
+{"id":1,"description":"Renew TLS","entry":"20260102T100000Z","modified":"20260103T100000Z","status":"pending","uuid":"0a1b2c3d-0000-4000-8000-000000000001","priority":"H","project":"ops","tags":["infra"],"scheduled":"20260102T100000Z","start":"20260103T100000Z","urgency":8.1,"annotations":[{"entry":"20260103T100000Z","description":"before March"}]},
+{"id":0,"description":"Old","entry":"20250102T100000Z","status":"deleted","uuid":"0a1b2c3d-0000-4000-8000-000000000002"},
+{"id":0,"description":"Pay rent","entry":"20250102T100000Z","end":"20250105T100000Z","status":"completed","uuid":"0a1b2c3d-0000-4000-8000-000000000003","priority":"X","project":"home"},
+{"id":2,"description":"Deploy","entry":"20260102T100000Z","status":"waiting","uuid":"0a1b2c3d-0000-4000-8000-000000000004","due":"20260301T120000Z"}
+]"#;
+
+    #[test]
+    fn test_read_export() {
+        let import = read_tasks(EXPORT.as_bytes()).unwrap();
+
+        assert_eq!(import.deleted, 1);
+        assert_eq!(
+            import.errors,
+            vec![RowError {
+                row: 3,
+                message: String::from("unknown priority \"X\", expected H, M or L"),
+            }]
+        );
+        assert_eq!(
+            import.ignored_fields,
+            BTreeMap::from([(String::from("scheduled"), 1)]),
+            "Only the imported tasks are counted"
+        );
+
+        let renew = &import.rows[0].task;
+        assert_eq!(renew.uuid(), Some("0a1b2c3d-0000-4000-8000-000000000001"));
+        assert_eq!(renew.get_name(), String::from("Renew TLS"));
+        assert_eq!(renew.get_description(), String::from("before March"));
+        assert_eq!(renew.tags(), &[String::from("infra")]);
+        assert_eq!(renew.project(), Some("ops"));
+        assert_eq!(renew.task_state(), Some(&TaskState::InProgress));
+        assert_eq!(renew.priority(), Some(&Priority::High));
+        assert_eq!(
+            renew.modification(),
+            &Date::from_utc_timestamp("20260103T100000Z").unwrap()
+        );
+
+        let deploy = &import.rows[1].task;
+        assert_eq!(import.rows[1].row, 4);
+        assert_eq!(deploy.task_state(), Some(&TaskState::Pending));
+        assert_eq!(
+            deploy.term(),
+            Date::from_utc_timestamp("20260301T120000Z").as_ref()
+        );
+    }
+
+    #[test]
+    fn test_read_lines_and_invalid_items() {
+        let lines = "{\"uuid\":\"a\",\"status\":\"pending\",\"description\":\"One\"}\n\n[1]\nnot json\n{\"uuid\":\"b\",\"status\":\"pending\"}\n";
+
+        let import = read_tasks(lines.as_bytes()).unwrap();
+
+        assert_eq!(import.rows.len(), 1);
+        assert_eq!(
+            import.errors.iter().map(|i| i.row).collect::<Vec<usize>>(),
+            vec![2, 3, 4]
+        );
+        assert!(matches!(
+            read_tasks("[{\"uuid\":".as_bytes()),
+            Err(TaskError::InvalidImport(_))
+        ));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let import = read_tasks(EXPORT.as_bytes()).unwrap();
+        let tasks = import
+            .rows
+            .iter()
+            .map(|i| i.task.clone())
+            .collect::<Vec<Task>>();
+        let mut out = Vec::new();
+
+        write_tasks(&mut out, &tasks).unwrap();
+        let again = read_tasks(out.as_slice()).unwrap();
+
+        assert!(again.errors.is_empty());
+        assert!(again.ignored_fields.is_empty());
+        for (i, j) in tasks.iter().zip(again.rows.iter()) {
+            assert_eq!(i.to_vec()[2..], j.task.to_vec()[2..]);
+            assert_eq!(i.uuid(), j.task.uuid());
+            assert_eq!(i.tags(), j.task.tags());
+        }
+    }
+
+    #[test]
+    fn test_round_trip_keeps_urgent_and_missing_state() {
+        let urgent = ConcreteTaskBuilder::new()
+            .set_uuid(String::from("0a1b2c3d-0000-4000-8000-000000000005"))
+            .set_name(String::from("Urgent"))
+            .set_task_state(TaskState::InProgress)
+            .set_priority(Priority::Urgent)
+            .get_task();
+        let stateless = ConcreteTaskBuilder::new()
+            .set_uuid(String::from("0a1b2c3d-0000-4000-8000-000000000006"))
+            .set_name(String::from("No state"))
+            .get_task();
+        let tasks = vec![urgent, stateless];
+        let mut out = Vec::new();
+
+        write_tasks(&mut out, &tasks).unwrap();
+        let again = read_tasks(out.as_slice()).unwrap();
+
+        assert!(again.errors.is_empty());
+        assert!(again.ignored_fields.is_empty());
+        for (i, j) in tasks.iter().zip(again.rows.iter()) {
+            assert_eq!(i.to_vec()[2..], j.task.to_vec()[2..]);
+            assert_eq!(i.task_state(), j.task.task_state());
+            assert_eq!(i.priority(), j.task.priority());
+        }
+
+        // Changes made in Taskwarrior win over the fields written here.
+        let edited = String::from_utf8(out)
+            .unwrap()
+            .replace("\"priority\": \"H\"", "\"priority\": \"M\"")
+            .replace("\"status\": \"pending\"", "\"status\": \"completed\"");
+        let edited = read_tasks(edited.as_bytes()).unwrap();
+        assert_eq!(edited.rows[0].task.priority(), Some(&Priority::Normal));
+        assert_eq!(edited.rows[1].task.task_state(), Some(&TaskState::Ended));
+    }
+}
@@ -0,0 +1,285 @@
+use std::io::{BufRead, Write};
+
+use crate::model::delimited::{ImportedRow, RowError};
+use crate::model::error::TaskError;
+use crate::{ConcreteTaskBuilder, Date, Priority, Task, TaskBuilder, TaskState};
+
+// todo.txt has the priorities A to Z, only the first four are used here.
+fn letter_priority(letter: &str) -> Result<Priority, String> {
+    let mut chars = letter.chars();
+    return match (chars.next(), chars.next()) {
+        (Some(i), None) => Priority::from_letter(i)
+            .ok_or_else(|| format!("unknown priority ({}), expected (A) to (D)", i)),
+        _ => Err(format!("unknown priority {:?}, expected A to D", letter)),
+    };
+}
+
+// Follows the todo.txt line format:
+//   [x completion-date] [(A)] [creation-date] text [due:YYYY-MM-DD]
+// A completed task keeps its priority as pri:A, as todo.txt clients do, and
+// the modification date is used as both the completion and the creation date.
+// The description has no place in the format and is not written.
+pub fn to_line(task: &Task) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let ended = task.task_state() == Some(&TaskState::Ended);
+    if ended {
+        parts.push(String::from("x"));
+        parts.push(task.modification().to_iso_string());
+    } else if let Some(i) = task.priority() {
+        parts.push(format!("({})", i.to_letter()));
+    }
+    parts.push(task.modification().to_iso_string());
+    parts.push(
+        task.get_name()
+            .split_whitespace()
+            .map(|i| escape(i, ended))
+            .collect::<Vec<String>>()
+            .join(" "),
+    );
+    if let Some(i) = task.term() {
+        parts.push(format!("due:{}", i.to_iso_string()));
+    }
+    if let (true, Some(i)) = (ended, task.priority()) {
+        parts.push(format!("pri:{}", i.to_letter()));
+    }
+    return parts.join(" ");
+}
+
+fn priority_letter(token: &str) -> Option<&str> {
+    return token
+        .strip_prefix("pri:")
+        .filter(|i| i.len() == 1 && i.chars().all(|j| j.is_ascii_uppercase()));
+}
+
+// A word of the name that would be read as the term or the priority, with
+// any backslashes before it, gets one more backslash that reading removes.
+fn is_escaped_key(token: &str, ended: bool) -> bool {
+    let token = token.trim_start_matches('\\');
+    return token.strip_prefix("due:").and_then(parse_date).is_some()
+        || (ended && priority_letter(token).is_some());
+}
+
+fn escape(token: &str, ended: bool) -> String {
+    if is_escaped_key(token, ended) {
+        return format!("\\{}", token);
+    }
+    return String::from(token);
+}
+
+fn unescape(token: &str, ended: bool) -> &str {
+    return match token.strip_prefix('\\') {
+        Some(i) if is_escaped_key(i, ended) => i,
+        _ => token,
+    };
+}
+
+// The tags and pairs that todo.txt clients put after the text.
+fn is_metadata(token: &str) -> bool {
+    if token.len() > 1 && (token.starts_with('+') || token.starts_with('@')) {
+        return true;
+    }
+    return token
+        .split_once(':')
+        .is_some_and(|(key, value)| !key.is_empty() && !value.is_empty());
+}
+
+fn is_priority(token: &str) -> bool {
+    let chars: Vec<char> = token.chars().collect();
+    return chars.len() == 3 && chars[0] == '(' && chars[2] == ')' && chars[1].is_ascii_uppercase();
+}
+
+fn parse_date(token: &str) -> Option<Date> {
+    if token.len() != 10 {
+        return None;
+    }
+    return Date::from_iso_string(token).ok();
+}
+
+// +project, @context and the key:value pairs that are not known here are kept
+// in the name, where they were, so writing the task again gives them back.
+pub fn from_line(line: &str) -> Result<Task, String> {
+    let mut tokens = line.split_whitespace().peekable();
+    let mut builder = ConcreteTaskBuilder::new();
+    let mut creation = None;
+
+    let ended = tokens.peek() == Some(&"x");
+    if ended {
+        tokens.next();
+        builder = builder.set_task_state(TaskState::Ended);
+        let completion = tokens.peek().and_then(|i| parse_date(i));
+        if completion.is_some() {
+            tokens.next();
+            creation = completion;
+        }
+    }
+    if !ended && tokens.peek().is_some_and(|i| is_priority(i)) {
+        let token = tokens.next().unwrap_or_default();
+        builder = builder.set_priority(letter_priority(&token[1..2])?);
+    }
+    if let Some(i) = tokens.peek().and_then(|i| parse_date(i)) {
+        tokens.next();
+        creation = Some(i);
+    }
+
+    // The term and the priority are only read among the +project, @context
+    // and key:value words that end the line, anything before them is the
+    // name. A due: that is not a date, or a pri: that is not a letter, is
+    // text like any other pair.
+    let tokens = tokens.collect::<Vec<&str>>();
+    let mut metadata = tokens.len();
+    while metadata > 0 && is_metadata(tokens[metadata - 1]) {
+        metadata -= 1;
+    }
+    let mut text: Vec<&str> = Vec::new();
+    for (position, i) in tokens.iter().enumerate() {
+        let date = i.strip_prefix("due:").and_then(parse_date);
+        let letter = priority_letter(i).filter(|_| ended);
+        match (position >= metadata, date, letter) {
+            (true, Some(j), _) => builder = builder.set_term(j),
+            (true, _, Some(j)) => builder = builder.set_priority(letter_priority(j)?),
+            _ => text.push(unescape(i, ended)),
+        }
+    }
+    if text.is_empty() {
+        return Err(String::from("the task has no text"));
+    }
+    builder = builder.set_name(text.join(" "));
+    if let Some(i) = creation {
+        builder = builder.set_modification(i);
+    }
+    return Ok(builder.get_task());
+}
+
+pub fn write_tasks<W: Write>(out: &mut W, tasks: &[Task]) -> Result<(), TaskError> {
+    for i in tasks {
+        writeln!(out, "{}", to_line(i))?;
+    }
+    return Ok(());
+}
+
+// Rows are the line numbers of the file, blank lines are skipped.
+pub fn read_tasks<R: BufRead>(reader: R) -> Result<(Vec<ImportedRow>, Vec<RowError>), TaskError> {
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match from_line(&line) {
+            Ok(task) => rows.push(ImportedRow { row: i + 1, task }),
+            Err(message) => errors.push(RowError {
+                row: i + 1,
+                message,
+            }),
+        }
+    }
+    return Ok((rows, errors));
+}
+
+#[cfg(test)]
+mod tests_todotxt {
+    use super::*;
+
+    #[test]
+    fn test_from_line() {
+        let task =
+            from_line("(B) 2026-01-02 Call mom +family @phone due:2026-02-03 tel:555").unwrap();
+
+        assert_eq!(
+            task.get_name(),
+            String::from("Call mom +family @phone tel:555")
+        );
+        assert_eq!(task.priority(), Some(&Priority::High));
+        assert_eq!(
+            task.term(),
+            Some(&Date::from_iso_string("2026-02-03").unwrap())
+        );
+        assert_eq!(
+            task.modification(),
+            &Date::from_iso_string("2026-01-02").unwrap()
+        );
+        assert_eq!(task.task_state(), None);
+    }
+
+    #[test]
+    fn test_completed_line() {
+        let task = from_line("x 2026-03-04 2026-01-02 Pay rent pri:A @home").unwrap();
+
+        assert_eq!(task.task_state(), Some(&TaskState::Ended));
+        assert_eq!(task.priority(), Some(&Priority::Urgent));
+        assert_eq!(task.get_name(), String::from("Pay rent @home"));
+        assert_eq!(
+            task.modification(),
+            &Date::from_iso_string("2026-01-02").unwrap()
+        );
+        assert_eq!(
+            from_line("x Pay rent").unwrap().modification(),
+            &Date::get_local_date(),
+            "Without dates the task is new today"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for line in [
+            "(A) 2026-01-02 Call mom +family @phone tel:555 due:2026-02-03",
+            "x 2026-01-02 2026-01-02 Pay rent @home pri:D",
+            "2026-01-02 Read the book",
+            "2026-01-02 call re: due:soon",
+            "x 2026-01-02 2026-01-02 ask about pri:high pri:A",
+            "x 2026-01-02 2026-01-02 ask about \\pri:B",
+            "2026-01-02 renew \\due:2026-01-01",
+            "2026-01-02 renew \\\\due:2026-01-01 due:2026-02-03",
+        ] {
+            assert_eq!(to_line(&from_line(line).unwrap()), String::from(line));
+        }
+
+        let ask = ConcreteTaskBuilder::new()
+            .set_name(String::from("ask about pri:B"))
+            .set_task_state(TaskState::Ended)
+            .get_task();
+        let renew = ConcreteTaskBuilder::new()
+            .set_name(String::from("renew due:2026-01-01"))
+            .get_task();
+        for task in [ask, renew] {
+            let again = from_line(&to_line(&task)).unwrap();
+            assert_eq!(
+                again.to_vec()[2..],
+                task.to_vec()[2..],
+                "Words of the name are not read as the term or the priority"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        assert_eq!(
+            from_line("(E) Water plants"),
+            Err(String::from("unknown priority (E), expected (A) to (D)"))
+        );
+        let plants = from_line("Water plants due:tomorrow").unwrap();
+        assert_eq!(
+            (plants.get_name(), plants.term()),
+            (String::from("Water plants due:tomorrow"), None),
+            "A due: that is not a date stays in the name"
+        );
+        assert_eq!(
+            from_line("(A) 2026-01-02 +garden").unwrap().get_name(),
+            String::from("+garden"),
+            "A project alone is still text"
+        );
+        assert_eq!(
+            from_line("(A) 2026-01-02"),
+            Err(String::from("the task has no text"))
+        );
+
+        let (rows, errors) =
+            read_tasks("Water plants\n\n(E) Feed cat\nWalk dog\n".as_bytes()).unwrap();
+        assert_eq!(
+            rows.iter().map(|i| i.row).collect::<Vec<usize>>(),
+            vec![1, 4]
+        );
+        assert_eq!(errors[0].row, 3);
+    }
+}
@@ -1,139 +1,1186 @@
-use crate::Task;
+use crate::model::delimited;
+mod graph;
+mod markdown;
+mod org;
+
+use crate::model::critical_path::CriticalPath;
+use crate::model::error::TaskError;
+use crate::model::ical;
+use crate::model::project::{self, ProjectSummary};
+use crate::model::report_column::ReportColumn;
+use crate::model::search::{SearchHit, HIGHLIGHT_END, HIGHLIGHT_START};
+use crate::model::taskwarrior;
+use crate::model::todotxt;
+use crate::{Date, Task, TaskState};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, IsTerminal, Write};
+use terminal_size::{terminal_size, Width};
 use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Raise it whenever a field of the JSON output is renamed, removed or changes
+// its type; adding a field keeps the version.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Jsonl,
+    Csv,
+    Tsv,
+    Todotxt,
+    Taskwarrior,
+    Ics,
+    Markdown,
+    MarkdownChecklist,
+    Org,
+}
+
+impl OutputFormat {
+    pub fn to_str(&self) -> &str {
+        return match self {
+            Self::Table => "table",
+            Self::Json => "json",
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+            Self::Todotxt => "todotxt",
+            Self::Taskwarrior => "taskwarrior",
+            Self::Ics => "ics",
+            Self::Markdown => "markdown",
+            Self::MarkdownChecklist => "markdown-checklist",
+            Self::Org => "org",
+        };
+    }
+
+    pub fn delimiter(&self) -> Option<u8> {
+        return match self {
+            Self::Csv => Some(b','),
+            Self::Tsv => Some(b'\t'),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, ValueEnum)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+#[derive(Serialize)]
+struct TasksDocument<'a> {
+    schema_version: u32,
+    tasks: &'a [Task],
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct SearchRecord<'a> {
+    task: &'a Task,
+    snippet: String,
+    highlights: Vec<[usize; 2]>,
+}
+
+#[derive(Serialize)]
+struct SearchDocument<'a> {
+    schema_version: u32,
+    results: Vec<SearchRecord<'a>>,
+}
+
+#[derive(Serialize)]
+struct TagRecord<'a> {
+    name: &'a str,
+    tasks: u64,
+}
+
+#[derive(Serialize)]
+struct TagsDocument<'a> {
+    schema_version: u32,
+    tags: &'a [TagRecord<'a>],
+}
+
+#[derive(Serialize)]
+struct ProjectsDocument<'a> {
+    schema_version: u32,
+    projects: &'a [ProjectSummary],
+}
+
+#[derive(Serialize)]
+struct DependencyRecord<'a> {
+    blocker: &'a Task,
+    blocked: &'a Task,
+}
+
+#[derive(Serialize)]
+struct DependenciesDocument<'a> {
+    schema_version: u32,
+    dependencies: &'a [DependencyRecord<'a>],
+}
 
-const COLUMN_ID_SIZE: usize = 20;
-const COLUMN_NAME_SIZE: usize = 20;
-const COLUMN_MODIFICATION_SIZE: usize = 12;
-const COLUMN_TERM_SIZE: usize = 10;
-const COLUMN_TASK_STATE_SIZE: usize = 11;
-const COLUMN_PRIORITY_SIZE: usize = 8;
-const COLUMN_DESCRIPTION_SIZE: usize = 40;
-
-const HEADER_FIELDS: [&str; 7] = [
-    "ID",
-    "Name",
-    "Modification",
-    "Term",
-    "Task State",
-    "Priority",
-    "Description",
-];
-
-const HEADER_SIZES: [usize; 7] = [
-    COLUMN_ID_SIZE,
-    COLUMN_NAME_SIZE,
-    COLUMN_MODIFICATION_SIZE,
-    COLUMN_TERM_SIZE,
-    COLUMN_TASK_STATE_SIZE,
-    COLUMN_PRIORITY_SIZE,
-    COLUMN_DESCRIPTION_SIZE,
-];
-
-pub fn show_query_tasks(tasks: &Vec<Task>) {
-    show_divisor();
-    show_header();
-    show_each_tasks(tasks);
-}
-fn show_each_tasks(tasks: &Vec<Task>) {
-    for i in tasks.iter() {
-        show_divisor();
-        show_task(&i);
-    }
-    show_divisor();
-}
-fn show_header() {
-    let mut header: String = String::new();
-    for (i, j) in HEADER_FIELDS.iter().enumerate() {
-        let padding = HEADER_SIZES[i].saturating_sub(j.len());
-        let left_padding = padding / 2;
-        let right_padding = padding - left_padding;
-
-        let centered = format!(
-            "|{:>widthl$}{}{:>widthr$}",
-            "",
-            j,
-            "",
-            widthl = left_padding as usize,
-            widthr = right_padding as usize
-        );
-        header += centered.as_str();
-    }
-    header += "|";
-    println!("{}", header);
-}
-
-fn show_divisor() {
-    let mut header = String::new();
-    for i in HEADER_SIZES {
-        let space = format!("|{}", "=".repeat(i as usize));
-        header += space.as_str();
-    }
-    header += "|";
-    println!("{}", header);
-}
-
-fn show_task(task: &Task) {
-    let formated = format_to_square(task);
-    let matrix = get_sliced_cells(formated);
-
-    let mut task_info: String = String::new();
-    for i in 0..matrix[0].len() {
-        for j in 0..matrix.len() {
-            task_info += format!("|{}", matrix[j][i].clone()).as_str();
+#[derive(Serialize)]
+struct PathRecord<'a> {
+    tasks: Vec<&'a Task>,
+    days_left: i64,
+    slack: i64,
+}
+
+#[derive(Serialize)]
+struct CriticalPathDocument<'a> {
+    schema_version: u32,
+    critical_path: Option<&'a PathRecord<'a>>,
+}
+
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+const PLAIN_COLUMN_GAP: &str = "  ";
+
+// The narrowest a column gets when the terminal is too small for the whole
+// table, wider cells wrap inside it.
+fn minimum_width(column: &ReportColumn) -> usize {
+    return match column {
+        ReportColumn::Number => 3,
+        ReportColumn::Id => 3,
+        ReportColumn::Name => 10,
+        ReportColumn::Modification => 12,
+        ReportColumn::Term => 10,
+        ReportColumn::TaskState => 7,
+        ReportColumn::Priority => 8,
+        ReportColumn::Description => 11,
+        ReportColumn::Tags => 6,
+        ReportColumn::Project => 7,
+        ReportColumn::DaysLeft => 4,
+        ReportColumn::Age => 3,
+    };
+}
+
+struct Cell {
+    column: ReportColumn,
+    header: &'static str,
+    value: String,
+}
+
+// Days until the term, negative once it has passed, and days since the last
+// modification.
+fn derived_days(task: &Task, column: &ReportColumn, today: &Date) -> Option<i64> {
+    return match column {
+        ReportColumn::DaysLeft => today.days_until(task.term()?),
+        ReportColumn::Age => task.modification().days_until(today),
+        _ => None,
+    };
+}
+
+// The table writes absent fields as "None" and dates as the commands take
+// them, a record leaves them empty and writes ISO dates as in the files of
+// export.
+fn column_values(task: &Task, columns: &[ReportColumn], today: &Date, record: bool) -> Vec<String> {
+    let (fields, absent) = if record {
+        (task.to_record(), "")
+    } else {
+        (task.to_vec(), "None")
+    };
+    return columns
+        .iter()
+        .map(|i| match i.field_index() {
+            Some(j) => fields[j].clone(),
+            None => derived_days(task, i, today)
+                .map(|j| j.to_string())
+                .unwrap_or_else(|| String::from(absent)),
+        })
+        .collect();
+}
+
+fn report_cells(task: &Task, columns: &[ReportColumn]) -> Vec<Cell> {
+    return columns
+        .iter()
+        .zip(column_values(task, columns, &Date::get_local_date(), true))
+        .map(|(column, value)| Cell {
+            column: column.clone(),
+            header: column.header(),
+            value,
+        })
+        .collect();
+}
+
+pub fn show_tasks(
+    tasks: &[Task],
+    format: &OutputFormat,
+    columns: &[ReportColumn],
+) -> Result<(), TaskError> {
+    if *format == OutputFormat::Table {
+        show_query_tasks(tasks, columns);
+        return Ok(());
+    }
+    return write_tasks(&mut io::stdout().lock(), tasks, format, columns, "show");
+}
+
+// The table is only meant for a terminal, every other format can be written
+// to a file. The columns only apply to the formats made of them, JSON and
+// the files of other programs always carry the whole task.
+pub fn write_tasks<W: Write>(
+    out: &mut W,
+    tasks: &[Task],
+    format: &OutputFormat,
+    columns: &[ReportColumn],
+    command: &'static str,
+) -> Result<(), TaskError> {
+    return match format {
+        OutputFormat::Table => Err(TaskError::UnsupportedFormat {
+            command,
+            format: String::from(format.to_str()),
+        }),
+        OutputFormat::Json => write_json(
+            out,
+            &TasksDocument {
+                schema_version: JSON_SCHEMA_VERSION,
+                tasks,
+            },
+        ),
+        OutputFormat::Jsonl => write_lines(out, tasks),
+        OutputFormat::Csv | OutputFormat::Tsv => delimited::write_records(
+            out,
+            format.delimiter().unwrap_or(b','),
+            &columns.iter().map(|i| i.header()).collect::<Vec<&str>>(),
+            &tasks
+                .iter()
+                .map(|i| {
+                    report_cells(i, columns)
+                        .into_iter()
+                        .map(|j| j.value)
+                        .collect()
+                })
+                .collect::<Vec<Vec<String>>>(),
+        ),
+        OutputFormat::Todotxt => todotxt::write_tasks(out, tasks),
+        OutputFormat::Taskwarrior => taskwarrior::write_tasks(out, tasks),
+        OutputFormat::Ics => ical::write_tasks(out, tasks),
+        OutputFormat::Markdown => markdown::write_table(out, tasks, columns),
+        OutputFormat::MarkdownChecklist => markdown::write_checklist(out, tasks, columns),
+        OutputFormat::Org => org::write_tasks(out, tasks, columns),
+    };
+}
+
+// Dependencies are pairs of blocker and blocked ids, the critical path is
+// marked in the graph and summed up in a comment at its top.
+pub fn write_graph<W: Write>(
+    out: &mut W,
+    format: &GraphFormat,
+    tasks: &[Task],
+    dependencies: &[(u64, u64)],
+    path: Option<&CriticalPath>,
+) -> Result<(), TaskError> {
+    return match format {
+        GraphFormat::Dot => graph::write_dot(out, tasks, dependencies, path),
+        GraphFormat::Mermaid => graph::write_mermaid(out, tasks, dependencies, path),
+    };
+}
+
+// With JSON the tasks of the path are written whole, and the path is null
+// when no open task has a term.
+pub fn show_critical_path(
+    tasks: &[Task],
+    path: Option<&CriticalPath>,
+    format: &OutputFormat,
+) -> Result<(), TaskError> {
+    if *format == OutputFormat::Table {
+        show_critical_path_table(tasks, path);
+        return Ok(());
+    }
+    let record = path.map(|i| PathRecord {
+        tasks: i
+            .tasks
+            .iter()
+            .filter_map(|j| tasks.iter().find(|k| k.get_id() == *j))
+            .collect(),
+        days_left: i.days_left,
+        slack: i.slack,
+    });
+    let document = CriticalPathDocument {
+        schema_version: JSON_SCHEMA_VERSION,
+        critical_path: record.as_ref(),
+    };
+    return write_listing(
+        &document,
+        record.as_slice(),
+        format,
+        "graph --critical-path",
+    );
+}
+
+fn show_critical_path_table(tasks: &[Task], path: Option<&CriticalPath>) {
+    let Some(path) = path else {
+        println!("No open task in the graph has a term");
+        return;
+    };
+    println!("{}", graph::path_summary(tasks, path));
+    let today = Date::get_local_date();
+    for i in path
+        .tasks
+        .iter()
+        .filter_map(|i| tasks.iter().find(|j| j.get_id() == *i))
+    {
+        let term = match i.term().and_then(|j| today.days_until(j)) {
+            Some(j) if j < 0 => format!("  term {}, {} days ago", i.get_term(), -j),
+            Some(j) => format!("  term {}, {} days left", i.get_term(), j),
+            None => String::new(),
+        };
+        println!("#{}  {}{}", i.get_number(), i.get_name(), term);
+    }
+}
+
+pub fn show_search(hits: &[SearchHit], format: &OutputFormat) -> Result<(), TaskError> {
+    let records = hits
+        .iter()
+        .map(search_record)
+        .collect::<Vec<SearchRecord>>();
+    return match format {
+        OutputFormat::Table => {
+            show_search_results(hits);
+            Ok(())
         }
-        if i == matrix[0].len() - 1 {
-            task_info += "|";
+        OutputFormat::Json => write_json(
+            &mut io::stdout().lock(),
+            &SearchDocument {
+                schema_version: JSON_SCHEMA_VERSION,
+                results: records,
+            },
+        ),
+        OutputFormat::Jsonl => write_lines(&mut io::stdout().lock(), &records),
+        _ => Err(TaskError::UnsupportedFormat {
+            command: "search",
+            format: String::from(format.to_str()),
+        }),
+    };
+}
+
+// The lists other than the tasks are printed by their commands, this writes
+// them for the formats read by scripts.
+fn write_listing<D: Serialize, R: Serialize>(
+    document: &D,
+    records: &[R],
+    format: &OutputFormat,
+    command: &'static str,
+) -> Result<(), TaskError> {
+    return match format {
+        OutputFormat::Json => write_json(&mut io::stdout().lock(), document),
+        OutputFormat::Jsonl => write_lines(&mut io::stdout().lock(), records),
+        _ => Err(TaskError::UnsupportedFormat {
+            command,
+            format: String::from(format.to_str()),
+        }),
+    };
+}
+
+pub fn write_tags(tags: &[(String, u64)], format: &OutputFormat) -> Result<(), TaskError> {
+    let records = tags
+        .iter()
+        .map(|(name, tasks)| TagRecord {
+            name,
+            tasks: *tasks,
+        })
+        .collect::<Vec<TagRecord>>();
+    let document = TagsDocument {
+        schema_version: JSON_SCHEMA_VERSION,
+        tags: &records,
+    };
+    return write_listing(&document, &records, format, "tags");
+}
+
+pub fn write_projects(projects: &[ProjectSummary], format: &OutputFormat) -> Result<(), TaskError> {
+    let document = ProjectsDocument {
+        schema_version: JSON_SCHEMA_VERSION,
+        projects,
+    };
+    return write_listing(&document, projects, format, "project list");
+}
+
+// Pairs of blocker and blocked tasks, each written whole.
+pub fn write_dependencies(
+    dependencies: &[(Task, Task)],
+    format: &OutputFormat,
+) -> Result<(), TaskError> {
+    let records = dependencies
+        .iter()
+        .map(|(blocker, blocked)| DependencyRecord { blocker, blocked })
+        .collect::<Vec<DependencyRecord>>();
+    let document = DependenciesDocument {
+        schema_version: JSON_SCHEMA_VERSION,
+        dependencies: &records,
+    };
+    return write_listing(&document, &records, format, "dependency list");
+}
+
+fn write_json<W: Write, T: Serialize>(out: &mut W, document: &T) -> Result<(), TaskError> {
+    serde_json::to_writer_pretty(&mut *out, document).map_err(io::Error::from)?;
+    writeln!(out)?;
+    return Ok(());
+}
+
+fn write_lines<W: Write, T: Serialize>(out: &mut W, records: &[T]) -> Result<(), TaskError> {
+    for i in records {
+        serde_json::to_writer(&mut *out, i).map_err(io::Error::from)?;
+        writeln!(out)?;
+    }
+    return Ok(());
+}
+
+// The offsets count characters of the snippet, not bytes.
+fn search_record(hit: &SearchHit) -> SearchRecord<'_> {
+    let mut snippet = String::new();
+    let mut highlights = Vec::new();
+    let mut start = 0;
+    let mut length = 0;
+    for i in hit.snippet.chars() {
+        if i == HIGHLIGHT_START {
+            start = length;
+        } else if i == HIGHLIGHT_END {
+            highlights.push([start, length]);
         } else {
-            task_info += "|\n";
+            snippet.push(i);
+            length += 1;
         }
     }
-    println!("{}", task_info);
+    return SearchRecord {
+        task: &hit.task,
+        snippet,
+        highlights,
+    };
 }
 
-fn get_sliced_cells(vec_task: Vec<String>) -> Vec<Vec<String>> {
-    let mut mat: Vec<Vec<String>> = Vec::new();
-    for (i, j) in vec_task.iter().enumerate() {
-        let words = j
-            .graphemes(true)
-            .collect::<Vec<_>>()
-            .chunks(HEADER_SIZES[i])
-            .map(|chunk| {
-                let mut word: String = String::new();
-                for i in chunk {
-                    word += i;
-                }
-                return word;
-            })
-            .collect::<Vec<String>>();
-        mat.push(words);
+// A terminal gets the bordered table fitted to its width, anything else one
+// line per task so the output can go through grep, cut or a pager.
+fn show_query_tasks(tasks: &[Task], columns: &[ReportColumn]) {
+    let today = Date::get_local_date();
+    let rows = tasks
+        .iter()
+        .map(|i| column_values(i, columns, &today, false))
+        .collect::<Vec<Vec<String>>>();
+    show_rows(columns, &rows);
+}
+
+fn show_rows(columns: &[ReportColumn], rows: &[Vec<String>]) {
+    if io::stdout().is_terminal() {
+        print!("{}", format_table(columns, rows, terminal_width()));
+    } else {
+        print!("{}", format_plain(columns, rows));
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct TreeLine {
+    index: usize,
+    level: usize,
+    subtasks: usize,
+    ended: usize,
+    hidden: usize,
+}
+
+// Each task under its parent, the name indented by level and followed by the
+// subtasks ended below it. With a depth the deeper subtasks are left out and
+// counted as hidden.
+pub fn show_task_tree(tasks: &[Task], columns: &[ReportColumn], depth: Option<usize>) {
+    let today = Date::get_local_date();
+    let name_column = columns
+        .iter()
+        .position(|i| *i == ReportColumn::Name)
+        .unwrap_or(0);
+    let rows = tree_lines(tasks, depth)
+        .iter()
+        .map(|i| {
+            let mut row = column_values(&tasks[i.index], columns, &today, false);
+            if let Some(cell) = row.get_mut(name_column) {
+                *cell = format!("{}{}{}", "  ".repeat(i.level), cell, tree_suffix(i));
+            }
+            return row;
+        })
+        .collect::<Vec<Vec<String>>>();
+    show_rows(columns, &rows);
+}
+
+fn tree_suffix(line: &TreeLine) -> String {
+    if line.subtasks == 0 {
+        return String::new();
+    }
+    let hidden = if line.hidden > 0 {
+        format!(", {} hidden", line.hidden)
+    } else {
+        String::new()
+    };
+    return format!(
+        " ({}/{} subtasks ended{})",
+        line.ended, line.subtasks, hidden
+    );
+}
+
+// The tasks whose parent is not shown are the roots, kept in the order they
+// were given, and the subtasks follow their parent in the same order.
+fn tree_lines(tasks: &[Task], depth: Option<usize>) -> Vec<TreeLine> {
+    let positions: HashMap<u64, usize> = tasks
+        .iter()
+        .enumerate()
+        .map(|(i, task)| (task.get_id(), i))
+        .collect();
+    let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (i, task) in tasks.iter().enumerate() {
+        match task.parent().and_then(|j| positions.get(&j)) {
+            Some(j) if *j != i => children.entry(*j).or_default().push(i),
+            _ => roots.push(i),
+        }
+    }
+    let mut lines = Vec::new();
+    let mut visited = HashSet::new();
+    for i in roots {
+        add_tree_line(tasks, &children, i, 0, depth, &mut visited, &mut lines);
+    }
+    // Only a cycle leaves tasks out, they are shown at the end as roots.
+    for i in 0..tasks.len() {
+        if !visited.contains(&i) {
+            add_tree_line(tasks, &children, i, 0, depth, &mut visited, &mut lines);
+        }
+    }
+    return lines;
+}
+
+fn add_tree_line(
+    tasks: &[Task],
+    children: &HashMap<usize, Vec<usize>>,
+    index: usize,
+    level: usize,
+    depth: Option<usize>,
+    visited: &mut HashSet<usize>,
+    lines: &mut Vec<TreeLine>,
+) {
+    if !visited.insert(index) {
+        return;
+    }
+    let position = lines.len();
+    lines.push(TreeLine {
+        index,
+        level,
+        subtasks: 0,
+        ended: 0,
+        hidden: 0,
+    });
+    if depth.is_none_or(|i| level < i) {
+        for i in children.get(&index).into_iter().flatten() {
+            let child = lines.len();
+            add_tree_line(tasks, children, *i, level + 1, depth, visited, lines);
+            if let Some(line) = lines.get(child).filter(|j| j.index == *i) {
+                let ended = tasks[*i].task_state() == Some(&TaskState::Ended);
+                let (subtasks, hidden) = (line.subtasks + 1, line.hidden);
+                let ended = line.ended + ended as usize;
+                lines[position].subtasks += subtasks;
+                lines[position].ended += ended;
+                lines[position].hidden += hidden;
+            }
+        }
+    } else {
+        let mut below = Vec::new();
+        collect_subtasks(children, index, visited, &mut below);
+        lines[position].subtasks = below.len();
+        lines[position].ended = below
+            .iter()
+            .filter(|i| tasks[**i].task_state() == Some(&TaskState::Ended))
+            .count();
+        lines[position].hidden = below.len();
+    }
+}
+
+fn collect_subtasks(
+    children: &HashMap<usize, Vec<usize>>,
+    index: usize,
+    visited: &mut HashSet<usize>,
+    below: &mut Vec<usize>,
+) {
+    for i in children.get(&index).into_iter().flatten() {
+        if visited.insert(*i) {
+            below.push(*i);
+            collect_subtasks(children, *i, visited, below);
+        }
+    }
+}
+
+// One heading with the progress of each project, then its tasks as show
+// gives them. The totals count the tasks shown, so they follow the filters.
+pub fn show_project_groups(tasks: &[Task], columns: &[ReportColumn]) {
+    for (i, group) in project_groups(tasks).iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let ended = group
+            .iter()
+            .filter(|j| j.task_state() == Some(&TaskState::Ended))
+            .count();
+        println!(
+            "{}: {}",
+            group[0].project().unwrap_or("No project"),
+            project::progress(ended as u64, group.len() as u64)
+        );
+        show_query_tasks(group, columns);
+    }
+}
+
+// Projects in the order of their names, the tasks without one last, and
+// the tasks of each group in the order they were given.
+fn project_groups(tasks: &[Task]) -> Vec<Vec<Task>> {
+    let mut groups: BTreeMap<(bool, String), Vec<Task>> = BTreeMap::new();
+    for i in tasks {
+        let key = (
+            i.project().is_none(),
+            i.project().unwrap_or_default().to_lowercase(),
+        );
+        groups.entry(key).or_default().push(i.clone());
+    }
+    return groups.into_values().collect();
+}
+
+// Matches are shown in bold on a terminal, and between brackets when the
+// output goes to a file or another program.
+fn show_search_results(hits: &[SearchHit]) {
+    let (start, end) = if io::stdout().is_terminal() {
+        ("\x1b[1m", "\x1b[0m")
+    } else {
+        ("[", "]")
+    };
+    for i in hits.iter() {
+        println!(
+            "#{} {} ({}, {})",
+            i.task.get_number(),
+            i.task.get_name(),
+            i.task.get_state(),
+            i.task.get_priority()
+        );
+        let snippet = i
+            .snippet
+            .replace(HIGHLIGHT_START, start)
+            .replace(HIGHLIGHT_END, end)
+            .replace('\n', " ");
+        println!("    {}", snippet);
+    }
+}
+
+fn terminal_width() -> usize {
+    if let Some((Width(i), _)) = terminal_size() {
+        return i as usize;
     }
-    return mat;
+    return std::env::var("COLUMNS")
+        .ok()
+        .and_then(|i| i.parse().ok())
+        .filter(|i| *i > 0)
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH);
 }
 
-fn format_to_square(task: &Task) -> Vec<String> {
-    let minimal_number = calculate_number_of_lines(task);
-    let infos = task.to_vec();
-    let mut new_lines: Vec<String> = Vec::new();
-    for i in 0..infos.len() {
-        let new_info = infos[i].clone()
-            + String::from(" ")
-                .repeat((minimal_number * HEADER_SIZES[i]) - infos[i].graphemes(true).count())
-                .as_str();
+fn format_table(columns: &[ReportColumn], rows: &[Vec<String>], terminal_width: usize) -> String {
+    let natural = natural_widths(columns, rows);
+    let borders = columns.len() + 1;
+    let widths = column_widths(
+        &natural,
+        &columns.iter().map(minimum_width).collect::<Vec<usize>>(),
+        terminal_width.saturating_sub(borders),
+    );
+    let divisor = widths
+        .iter()
+        .map(|i| format!("|{}", "=".repeat(*i)))
+        .collect::<String>()
+        + "|\n";
+    let header = columns
+        .iter()
+        .map(|i| String::from(i.header()))
+        .collect::<Vec<String>>();
 
-        new_lines.push(new_info);
+    let mut table = divisor.clone();
+    table += format_row(&header, &widths, true).as_str();
+    for i in rows {
+        table += divisor.as_str();
+        table += format_row(i, &widths, false).as_str();
     }
-    return new_lines;
+    table += divisor.as_str();
+    return table;
 }
 
-fn calculate_number_of_lines(task: &Task) -> usize {
-    let mut minimal_number = 1;
-    let infos = task.to_vec();
-    for (i, j) in infos.iter().enumerate() {
-        let lines = (j.graphemes(true).count() + HEADER_SIZES[i] - 1) / HEADER_SIZES[i];
-        if lines > minimal_number {
-            minimal_number = lines;
+// The widest line of each column, header included.
+fn natural_widths(columns: &[ReportColumn], rows: &[Vec<String>]) -> Vec<usize> {
+    let mut widths = columns
+        .iter()
+        .map(|i| i.header().width())
+        .collect::<Vec<usize>>();
+    for i in rows {
+        for (j, cell) in i.iter().enumerate() {
+            let width = cell
+                .replace('\t', " ")
+                .lines()
+                .map(|k| k.width())
+                .max()
+                .unwrap_or(0);
+            widths[j] = widths[j].max(width);
         }
     }
+    return widths;
+}
+
+// Columns keep their natural width when everything fits. Otherwise each one
+// starts from its minimum, or less when its content is narrower, and the
+// space left is shared in proportion to what every column still misses.
+fn column_widths(natural: &[usize], minimums: &[usize], available: usize) -> Vec<usize> {
+    if natural.iter().sum::<usize>() <= available {
+        return natural.to_vec();
+    }
+    let mut widths = natural
+        .iter()
+        .zip(minimums)
+        .map(|(i, j)| (*i).min(*j).max(1))
+        .collect::<Vec<usize>>();
+    let deficits = natural
+        .iter()
+        .zip(&widths)
+        .map(|(i, j)| i.saturating_sub(*j))
+        .collect::<Vec<usize>>();
+    let total = deficits.iter().sum::<usize>();
+    let spare = available.saturating_sub(widths.iter().sum()).min(total);
+    if total == 0 {
+        return widths;
+    }
+
+    let mut given = 0;
+    for (i, j) in widths.iter_mut().zip(&deficits) {
+        let share = spare * j / total;
+        *i += share;
+        given += share;
+    }
+    // What the rounding left goes one by one to the columns still short.
+    let mut left = spare - given;
+    for (i, j) in widths.iter_mut().zip(natural) {
+        if left == 0 {
+            break;
+        }
+        if *i < *j {
+            *i += 1;
+            left -= 1;
+        }
+    }
+    return widths;
+}
+
+// Breaks the text on word boundaries so no line is wider than width columns
+// of the terminal, a word that is wider by itself is cut between graphemes.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.replace('\t', " ").lines() {
+        let mut line = String::new();
+        let mut line_width = 0;
+        let mut wrapped = false;
+        for word in paragraph.split_word_bounds() {
+            let word_width = word.width();
+            if line_width + word_width > width && !line.is_empty() {
+                lines.push(String::from(line.trim_end()));
+                line.clear();
+                line_width = 0;
+                wrapped = true;
+            }
+            if line.is_empty() && wrapped && word.trim().is_empty() {
+                continue;
+            }
+            if word_width <= width {
+                line += word;
+                line_width += word_width;
+                continue;
+            }
+            for i in word.graphemes(true) {
+                let grapheme_width = i.width();
+                if line_width + grapheme_width > width && !line.is_empty() {
+                    lines.push(String::from(line.trim_end()));
+                    line.clear();
+                    line_width = 0;
+                }
+                line += i;
+                line_width += grapheme_width;
+            }
+        }
+        lines.push(String::from(line.trim_end()));
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    return lines;
+}
+
+fn pad(text: &str, width: usize, centered: bool) -> String {
+    let padding = width.saturating_sub(text.width());
+    let left = if centered { padding / 2 } else { 0 };
+    return format!("{}{}{}", " ".repeat(left), text, " ".repeat(padding - left));
+}
+
+fn format_row(cells: &[String], widths: &[usize], centered: bool) -> String {
+    let wrapped = cells
+        .iter()
+        .zip(widths)
+        .map(|(i, j)| wrap(i, *j))
+        .collect::<Vec<Vec<String>>>();
+    let height = wrapped.iter().map(Vec::len).max().unwrap_or(1);
 
-    return minimal_number;
+    let mut row = String::new();
+    for line in 0..height {
+        for (i, j) in wrapped.iter().zip(widths) {
+            let text = i.get(line).map(String::as_str).unwrap_or("");
+            row += format!("|{}", pad(text, *j, centered)).as_str();
+        }
+        row += "|\n";
+    }
+    return row;
+}
+
+// No borders and a line per task, the cells are only padded to line up.
+fn format_plain(columns: &[ReportColumn], rows: &[Vec<String>]) -> String {
+    let mut lines = vec![columns
+        .iter()
+        .map(|i| String::from(i.header()))
+        .collect::<Vec<String>>()];
+    for i in rows {
+        lines.push(
+            i.iter()
+                .map(|j| j.replace("\r\n", " ").replace(['\n', '\t'], " "))
+                .collect(),
+        );
+    }
+    let widths = (0..columns.len())
+        .map(|i| lines.iter().map(|j| j[i].width()).max().unwrap_or(0))
+        .collect::<Vec<usize>>();
+
+    let mut plain = String::new();
+    for i in lines {
+        let cells = i
+            .iter()
+            .zip(&widths)
+            .map(|(j, k)| pad(j, *k, false))
+            .collect::<Vec<String>>();
+        plain += cells.join(PLAIN_COLUMN_GAP).trim_end();
+        plain += "\n";
+    }
+    return plain;
+}
+
+#[cfg(test)]
+mod tests_view {
+    use super::*;
+    use crate::model::report_column::DEFAULT_COLUMNS;
+    use crate::{ConcreteTaskBuilder, TaskBuilder};
+
+    #[test]
+    fn test_search_record_highlights() {
+        let hit = SearchHit {
+            task: ConcreteTaskBuilder::new().get_task(),
+            snippet: String::from("Renew the \u{2}TLS\u{3} cértificate \u{2}now\u{3}"),
+        };
+
+        let record = search_record(&hit);
+
+        assert_eq!(
+            record.snippet,
+            String::from("Renew the TLS cértificate now")
+        );
+        assert_eq!(
+            record.highlights,
+            vec![[10, 13], [26, 29]],
+            "Offsets should count characters"
+        );
+    }
+
+    #[test]
+    fn test_column_widths() {
+        let minimums = [3, 6, 10];
+
+        assert_eq!(
+            column_widths(&[2, 20, 30], &minimums, 60),
+            vec![2, 20, 30],
+            "A table that fits keeps its widths"
+        );
+        assert_eq!(column_widths(&[2, 20, 30], &minimums, 10), vec![2, 6, 10]);
+
+        let widths = column_widths(&[2, 26, 50], &minimums, 38);
+        assert_eq!(widths.iter().sum::<usize>(), 38);
+        assert_eq!(
+            widths,
+            vec![2, 13, 23],
+            "The space left follows the deficits"
+        );
+    }
+
+    #[test]
+    fn test_wrap_on_word_boundaries() {
+        assert_eq!(
+            wrap("renew the certificate", 10),
+            vec!["renew the", "certificat", "e"]
+        );
+        assert_eq!(wrap("one two\nthree", 7), vec!["one two", "three"]);
+        assert_eq!(wrap("", 5), vec![""]);
+        assert_eq!(
+            wrap("e\u{301}e\u{301}e\u{301}", 2),
+            vec!["e\u{301}e\u{301}", "e\u{301}"],
+            "Combining marks stay with their letter"
+        );
+    }
+
+    #[test]
+    fn test_wrap_wide_characters() {
+        assert_eq!(wrap("日本語のタスク", 6), vec!["日本語", "の", "タスク"]);
+        assert_eq!(wrap("🚀🚀 go", 5), vec!["🚀🚀", "go"]);
+        assert_eq!(pad("日本", 6, true), String::from(" 日本 "));
+    }
+
+    #[test]
+    fn test_format_table_fits_the_width() {
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Renew the TLS certificate of the api"))
+            .set_description(String::from("before March\nand tell the team"))
+            .get_task();
+
+        let table = format_table(&DEFAULT_COLUMNS, &[task.to_vec()], 80);
+
+        assert!(table.lines().all(|i| i.width() <= 80));
+        assert!(table
+            .lines()
+            .all(|i| i.starts_with('|') && i.ends_with('|')));
+        assert!(table.contains("|Renew the "));
+    }
+
+    #[test]
+    fn test_format_plain() {
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Deploy"))
+            .set_description(String::from("first\nsecond"))
+            .set_tags(vec![String::from("ops")])
+            .set_project(String::from("Infra"))
+            .get_task();
+
+        let plain = format_plain(&DEFAULT_COLUMNS, &[task.to_vec()]);
+        let lines = plain.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("#     ID  "));
+        assert!(lines[1].ends_with("first second  ops   Infra"));
+        assert_eq!(lines[0].find("Name"), lines[1].find("Deploy"));
+    }
+
+    #[test]
+    fn test_selected_and_derived_columns() {
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Renew TLS"))
+            .set_term(Date::from_iso_string("2026-03-01").unwrap())
+            .set_modification(Date::from_iso_string("2026-02-20").unwrap())
+            .get_task();
+        let no_term = ConcreteTaskBuilder::new()
+            .set_name(String::from("Deploy"))
+            .set_modification(Date::from_iso_string("2026-02-25").unwrap())
+            .get_task();
+        let today = Date::from_iso_string("2026-02-26").unwrap();
+        let columns = [
+            ReportColumn::DaysLeft,
+            ReportColumn::Name,
+            ReportColumn::Age,
+        ];
+
+        assert_eq!(
+            column_values(&task, &columns, &today, false),
+            vec!["3", "Renew TLS", "6"]
+        );
+        assert_eq!(
+            column_values(&no_term, &columns, &today, false),
+            vec!["None", "Deploy", "1"]
+        );
+        assert_eq!(
+            column_values(&no_term, &columns, &today, true),
+            vec!["", "Deploy", "1"]
+        );
+        assert_eq!(
+            column_values(&task, &[ReportColumn::Term], &today, true),
+            vec!["2026-03-01"]
+        );
+
+        let plain = format_plain(&columns, &[column_values(&task, &columns, &today, false)]);
+        assert_eq!(
+            plain,
+            "Days Left  Name       Age\n3          Renew TLS  6\n"
+        );
+    }
+
+    #[test]
+    fn test_tree_lines() {
+        let root = ConcreteTaskBuilder::new()
+            .set_name(String::from("Release"))
+            .get_task();
+        let build = ConcreteTaskBuilder::new()
+            .set_name(String::from("Build"))
+            .set_parent(root.get_id())
+            .set_task_state(TaskState::Ended)
+            .get_task();
+        let test = ConcreteTaskBuilder::new()
+            .set_name(String::from("Test"))
+            .set_parent(root.get_id())
+            .get_task();
+        let unit = ConcreteTaskBuilder::new()
+            .set_name(String::from("Unit tests"))
+            .set_parent(test.get_id())
+            .set_task_state(TaskState::Ended)
+            .get_task();
+        let orphan = ConcreteTaskBuilder::new()
+            .set_name(String::from("Orphan"))
+            .set_parent(u64::MAX)
+            .get_task();
+        let tasks = vec![unit, orphan, test, root, build];
+
+        let lines = tree_lines(&tasks, None);
+        let order = lines
+            .iter()
+            .map(|i| (tasks[i.index].get_name(), i.level))
+            .collect::<Vec<(String, usize)>>();
+
+        assert_eq!(
+            order,
+            vec![
+                (String::from("Orphan"), 0),
+                (String::from("Release"), 0),
+                (String::from("Test"), 1),
+                (String::from("Unit tests"), 2),
+                (String::from("Build"), 1),
+            ]
+        );
+        assert_eq!(tree_suffix(&lines[1]), " (2/3 subtasks ended)");
+        assert_eq!(tree_suffix(&lines[2]), " (1/1 subtasks ended)");
+        assert_eq!(tree_suffix(&lines[0]), "");
+
+        let collapsed = tree_lines(&tasks, Some(0));
+        assert_eq!(collapsed.len(), 2);
+        assert_eq!(
+            tree_suffix(&collapsed[1]),
+            " (2/3 subtasks ended, 3 hidden)"
+        );
+    }
+
+    #[test]
+    fn test_project_groups() {
+        let task = |name: &str, project: Option<&str>| {
+            let mut builder = ConcreteTaskBuilder::new().set_name(String::from(name));
+            return match project {
+                Some(i) => builder.set_project(String::from(i)).get_task(),
+                None => builder.get_task(),
+            };
+        };
+        let tasks = vec![
+            task("Loose", None),
+            task("Deploy", Some("web")),
+            task("Migrate", Some("API")),
+            task("Release", Some("web")),
+        ];
+
+        let names = project_groups(&tasks)
+            .iter()
+            .map(|i| i.iter().map(Task::get_name).collect::<Vec<String>>())
+            .collect::<Vec<Vec<String>>>();
+
+        assert_eq!(
+            names,
+            vec![vec!["Migrate"], vec!["Deploy", "Release"], vec!["Loose"]]
+        );
+    }
+
+    #[test]
+    fn test_tags_document() {
+        let tags = [TagRecord {
+            name: "ops",
+            tasks: 3,
+        }];
+
+        let document = serde_json::to_value(TagsDocument {
+            schema_version: JSON_SCHEMA_VERSION,
+            tags: &tags,
+        })
+        .unwrap();
+
+        assert_eq!(
+            document,
+            serde_json::json!({"schema_version": 1, "tags": [{"name": "ops", "tasks": 3}]})
+        );
+    }
+
+    #[test]
+    fn test_projects_document() {
+        let projects = [ProjectSummary {
+            project: project::Project::new(String::from("ops")),
+            tasks: 2,
+            ended: 1,
+        }];
+
+        let document = serde_json::to_value(ProjectsDocument {
+            schema_version: JSON_SCHEMA_VERSION,
+            projects: &projects,
+        })
+        .unwrap();
+
+        assert_eq!(
+            document["projects"],
+            serde_json::json!([{
+                "name": "ops",
+                "description": null,
+                "priority": null,
+                "archived": false,
+                "tasks": 2,
+                "ended": 1
+            }]),
+            "The project fields sit next to the totals"
+        );
+    }
+
+    #[test]
+    fn test_dependencies_document() {
+        let (build, release) = (
+            ConcreteTaskBuilder::new().get_task(),
+            ConcreteTaskBuilder::new().get_task(),
+        );
+        let records = [DependencyRecord {
+            blocker: &build,
+            blocked: &release,
+        }];
+
+        let document = serde_json::to_value(DependenciesDocument {
+            schema_version: JSON_SCHEMA_VERSION,
+            dependencies: &records,
+        })
+        .unwrap();
+
+        let dependency = &document["dependencies"][0];
+        assert_eq!(dependency["blocker"]["id"], build.get_id().to_string());
+        assert_eq!(dependency["blocked"]["id"], release.get_id().to_string());
+    }
+
+    #[test]
+    fn test_critical_path_document() {
+        let release = ConcreteTaskBuilder::new().get_task();
+        let path = PathRecord {
+            tasks: vec![&release],
+            days_left: 3,
+            slack: -1,
+        };
+
+        let document = serde_json::to_value(CriticalPathDocument {
+            schema_version: JSON_SCHEMA_VERSION,
+            critical_path: Some(&path),
+        })
+        .unwrap();
+        let none = serde_json::to_value(CriticalPathDocument {
+            schema_version: JSON_SCHEMA_VERSION,
+            critical_path: None,
+        })
+        .unwrap();
+
+        let critical_path = &document["critical_path"];
+        assert_eq!(
+            critical_path["tasks"][0]["id"],
+            release.get_id().to_string()
+        );
+        assert_eq!(critical_path["days_left"], 3);
+        assert_eq!(critical_path["slack"], -1);
+        assert_eq!(none["critical_path"], serde_json::Value::Null);
+    }
 }
@@ -0,0 +1,277 @@
+use std::io::Write;
+
+use crate::model::critical_path::CriticalPath;
+use crate::model::error::TaskError;
+use crate::{Priority, Task, TaskState};
+
+// The fill tells the state and the border the priority, the critical path
+// is drawn with thicker lines.
+fn state_fill(task: &Task) -> &'static str {
+    return match task.task_state() {
+        None => "#ffffff",
+        Some(TaskState::Pending) => "#fff3bf",
+        Some(TaskState::InProgress) => "#d0ebff",
+        Some(TaskState::Ended) => "#d3f9d8",
+    };
+}
+
+fn priority_stroke(task: &Task) -> &'static str {
+    return match task.priority() {
+        None | Some(Priority::Normal) => "#495057",
+        Some(Priority::Low) => "#adb5bd",
+        Some(Priority::High) => "#f08c00",
+        Some(Priority::Urgent) => "#e03131",
+    };
+}
+
+// Ids name the nodes since only stored tasks have a number.
+fn node_id(task: &Task) -> String {
+    return format!("t{}", task.get_id());
+}
+
+// Tasks not stored yet have no number and are shown by name.
+fn reference(task: &Task) -> String {
+    return match task.number() {
+        Some(i) => format!("#{}", i),
+        None => task.get_name(),
+    };
+}
+
+fn title(task: &Task) -> String {
+    return match task.number() {
+        Some(i) => format!("#{} {}", i, task.get_name()),
+        None => task.get_name(),
+    };
+}
+
+fn label_lines(task: &Task) -> Vec<String> {
+    let mut lines = vec![title(task)];
+    if let Some(i) = task.term() {
+        lines.push(i.to_string());
+    }
+    return lines;
+}
+
+fn is_critical(path: Option<&CriticalPath>, task_id: u64) -> bool {
+    return path.is_some_and(|i| i.tasks.contains(&task_id));
+}
+
+fn is_critical_edge(path: Option<&CriticalPath>, blocker: u64, blocked: u64) -> bool {
+    return path.is_some_and(|i| i.tasks.windows(2).any(|j| j == [blocker, blocked]));
+}
+
+pub fn path_summary(tasks: &[Task], path: &CriticalPath) -> String {
+    let chain = path
+        .tasks
+        .iter()
+        .filter_map(|i| tasks.iter().find(|j| j.get_id() == *i))
+        .map(reference)
+        .collect::<Vec<String>>()
+        .join(" -> ");
+    let slack = if path.at_risk() {
+        format!("{} days late", -path.slack)
+    } else {
+        format!("{} days of slack", path.slack)
+    };
+    return format!("critical path {}, {}", chain, slack);
+}
+
+// Quotes and backslashes would end the label early, line breaks become the
+// \n of Graphviz.
+fn dot_escape(text: &str) -> String {
+    return text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("\r\n", "\n")
+        .replace('\n', "\\n");
+}
+
+pub fn write_dot<W: Write>(
+    out: &mut W,
+    tasks: &[Task],
+    dependencies: &[(u64, u64)],
+    path: Option<&CriticalPath>,
+) -> Result<(), TaskError> {
+    writeln!(out, "digraph tasks {{")?;
+    if let Some(i) = path {
+        writeln!(out, "  // {}", path_summary(tasks, i))?;
+    }
+    writeln!(out, "  rankdir=LR;")?;
+    writeln!(out, "  node [shape=box, style=\"rounded,filled\"];")?;
+    for i in tasks {
+        let label = label_lines(i)
+            .iter()
+            .map(|j| dot_escape(j))
+            .collect::<Vec<String>>()
+            .join("\\n");
+        let width = if is_critical(path, i.get_id()) { 3 } else { 1 };
+        writeln!(
+            out,
+            "  {} [label=\"{}\", fillcolor=\"{}\", color=\"{}\", penwidth={}];",
+            node_id(i),
+            label,
+            state_fill(i),
+            priority_stroke(i),
+            width
+        )?;
+    }
+    for (blocker, blocked) in edges(tasks, dependencies) {
+        let width = if is_critical_edge(path, blocker.get_id(), blocked.get_id()) {
+            3
+        } else {
+            1
+        };
+        writeln!(
+            out,
+            "  {} -> {} [penwidth={}];",
+            node_id(blocker),
+            node_id(blocked),
+            width
+        )?;
+    }
+    writeln!(out, "}}")?;
+    return Ok(());
+}
+
+// Mermaid takes entity codes inside a quoted label and <br> for a new line.
+fn mermaid_escape(text: &str) -> String {
+    return text
+        .replace('"', "#quot;")
+        .replace("\r\n", "\n")
+        .replace('\n', "<br>");
+}
+
+pub fn write_mermaid<W: Write>(
+    out: &mut W,
+    tasks: &[Task],
+    dependencies: &[(u64, u64)],
+    path: Option<&CriticalPath>,
+) -> Result<(), TaskError> {
+    writeln!(out, "flowchart LR")?;
+    if let Some(i) = path {
+        writeln!(out, "  %% {}", path_summary(tasks, i))?;
+    }
+    for i in tasks {
+        let label = label_lines(i)
+            .iter()
+            .map(|j| mermaid_escape(j))
+            .collect::<Vec<String>>()
+            .join("<br>");
+        writeln!(out, "  {}[\"{}\"]", node_id(i), label)?;
+    }
+    let mut critical_edges = Vec::new();
+    for (position, (blocker, blocked)) in edges(tasks, dependencies).iter().enumerate() {
+        writeln!(out, "  {} --> {}", node_id(blocker), node_id(blocked))?;
+        if is_critical_edge(path, blocker.get_id(), blocked.get_id()) {
+            critical_edges.push(position.to_string());
+        }
+    }
+    for i in tasks {
+        let width = if is_critical(path, i.get_id()) { 3 } else { 1 };
+        writeln!(
+            out,
+            "  style {} fill:{},stroke:{},stroke-width:{}px",
+            node_id(i),
+            state_fill(i),
+            priority_stroke(i),
+            width
+        )?;
+    }
+    if !critical_edges.is_empty() {
+        writeln!(
+            out,
+            "  linkStyle {} stroke-width:3px",
+            critical_edges.join(",")
+        )?;
+    }
+    return Ok(());
+}
+
+// Dependencies whose two tasks are drawn, in the order they were given.
+fn edges<'a>(tasks: &'a [Task], dependencies: &[(u64, u64)]) -> Vec<(&'a Task, &'a Task)> {
+    let find = |id: u64| tasks.iter().find(|i| i.get_id() == id);
+    return dependencies
+        .iter()
+        .filter_map(|(blocker, blocked)| Some((find(*blocker)?, find(*blocked)?)))
+        .collect();
+}
+
+#[cfg(test)]
+mod tests_graph {
+    use super::*;
+    use crate::{ConcreteTaskBuilder, Date, TaskBuilder};
+
+    fn tasks() -> Vec<Task> {
+        let build = ConcreteTaskBuilder::new()
+            .set_name(String::from("Build \"fast\""))
+            .set_task_state(TaskState::Ended)
+            .get_task();
+        let release = ConcreteTaskBuilder::new()
+            .set_name(String::from("Release"))
+            .set_priority(Priority::Urgent)
+            .set_term(Date {
+                day: 5,
+                month: 11,
+                year: 2026,
+            })
+            .get_task();
+        return vec![build, release];
+    }
+
+    #[test]
+    fn test_write_dot() {
+        let tasks = tasks();
+        let (build, release) = (tasks[0].get_id(), tasks[1].get_id());
+        let dependencies = [(build, release)];
+        let path = CriticalPath {
+            tasks: vec![release],
+            days_left: 4,
+            slack: 4,
+        };
+        let mut out = Vec::new();
+
+        write_dot(&mut out, &tasks, &dependencies, Some(&path)).unwrap();
+
+        let dot = String::from_utf8(out).unwrap();
+        assert_ne!(build, release);
+        assert!(dot.starts_with("digraph tasks {\n  // critical path Release, 4 days of slack\n"));
+        assert!(dot.contains(&format!(
+            "  t{} [label=\"Build \\\"fast\\\"\", fillcolor=\"#d3f9d8\", color=\"#495057\", penwidth=1];",
+            build
+        )));
+        assert!(dot.contains(&format!(
+            "  t{} [label=\"Release\\n5-11-2026\", fillcolor=\"#ffffff\", color=\"#e03131\", penwidth=3];",
+            release
+        )));
+        assert!(dot.contains(&format!("  t{} -> t{} [penwidth=1];", build, release)));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_write_mermaid() {
+        let tasks = tasks();
+        let (build, release) = (tasks[0].get_id(), tasks[1].get_id());
+        let dependencies = [(build, release)];
+        let path = CriticalPath {
+            tasks: vec![build, release],
+            days_left: 0,
+            slack: -1,
+        };
+        let mut out = Vec::new();
+
+        write_mermaid(&mut out, &tasks, &dependencies, Some(&path)).unwrap();
+
+        let mermaid = String::from_utf8(out).unwrap();
+        assert!(mermaid.starts_with(
+            "flowchart LR\n  %% critical path Build \"fast\" -> Release, 1 days late\n"
+        ));
+        assert!(mermaid.contains(&format!("  t{}[\"Build #quot;fast#quot;\"]", build)));
+        assert!(mermaid.contains(&format!("  t{}[\"Release<br>5-11-2026\"]", release)));
+        assert!(mermaid.contains(&format!("  t{} --> t{}\n", build, release)));
+        assert!(mermaid.contains(&format!(
+            "  style t{} fill:#ffffff,stroke:#e03131,stroke-width:3px",
+            release
+        )));
+        assert!(mermaid.ends_with("  linkStyle 0 stroke-width:3px\n"));
+    }
+}
@@ -0,0 +1,167 @@
+use std::io::Write;
+
+use super::{report_cells, Cell};
+use crate::model::error::TaskError;
+use crate::model::report_column::ReportColumn;
+use crate::{Task, TaskState};
+
+// Backslashes keep names such as "fix_*all*" from turning into emphasis.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for i in text.chars() {
+        if "\\`*_[]<>|".contains(i) {
+            escaped.push('\\');
+        }
+        escaped.push(i);
+    }
+    return escaped;
+}
+
+// A GitHub table cell has to fit in one line.
+fn table_cell(text: &str) -> String {
+    return escape(text).replace("\r\n", "\n").replace('\n', "<br>");
+}
+
+pub fn write_table<W: Write>(
+    out: &mut W,
+    tasks: &[Task],
+    columns: &[ReportColumn],
+) -> Result<(), TaskError> {
+    let headers = columns
+        .iter()
+        .map(|i| table_cell(i.header()))
+        .collect::<Vec<String>>();
+    writeln!(out, "| {} |", headers.join(" | "))?;
+    writeln!(out, "|{}", " --- |".repeat(headers.len()))?;
+    for i in tasks {
+        let values = report_cells(i, columns)
+            .iter()
+            .map(|j| table_cell(&j.value))
+            .collect::<Vec<String>>();
+        writeln!(out, "| {} |", values.join(" | "))?;
+    }
+    return Ok(());
+}
+
+// One item per task, the number and the name make its text, the other
+// columns follow in parentheses and the description goes below it.
+fn checklist_item(task: &Task, cells: &[Cell]) -> String {
+    let mark = if task.task_state() == Some(&TaskState::Ended) {
+        "x"
+    } else {
+        " "
+    };
+    let mut title = Vec::new();
+    let mut details = Vec::new();
+    let mut description = None;
+    for i in cells.iter().filter(|i| !i.value.is_empty()) {
+        match i.column {
+            ReportColumn::Number => title.push(format!("#{}", i.value)),
+            ReportColumn::Name => title.push(escape(&i.value)),
+            ReportColumn::Description => description = Some(&i.value),
+            _ => details.push(format!("{}: {}", i.header, escape(&i.value))),
+        }
+    }
+    let mut item = format!("- [{}] {}", mark, title.join(" "));
+    if !details.is_empty() {
+        item += format!(" ({})", details.join(", ")).as_str();
+    }
+    if let Some(i) = description {
+        for line in i.lines() {
+            item += format!("\n  {}", escape(line)).as_str();
+        }
+    }
+    return item;
+}
+
+pub fn write_checklist<W: Write>(
+    out: &mut W,
+    tasks: &[Task],
+    columns: &[ReportColumn],
+) -> Result<(), TaskError> {
+    for i in tasks {
+        writeln!(out, "{}", checklist_item(i, &report_cells(i, columns)))?;
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests_markdown {
+    use super::*;
+    use crate::model::report_column::DEFAULT_COLUMNS;
+    use crate::{ConcreteTaskBuilder, Date, Priority, TaskBuilder};
+
+    fn task() -> Task {
+        return ConcreteTaskBuilder::new()
+            .set_name(String::from("Fix a|b *now*"))
+            .set_description(String::from("first\nsecond"))
+            .set_term(Date::from_iso_string("2026-03-01").unwrap())
+            .set_priority(Priority::High)
+            .set_task_state(TaskState::Ended)
+            .set_modification(Date::from_iso_string("2026-01-02").unwrap())
+            .set_tags(vec![String::from("ops"), String::from("ui")])
+            .get_task();
+    }
+
+    #[test]
+    fn test_table() {
+        let task = task();
+        let mut out = Vec::new();
+
+        write_table(&mut out, std::slice::from_ref(&task), &DEFAULT_COLUMNS).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "| # | ID | Name | Modification | Term | Task State | Priority | Description | Tags | Project |\n| --- | --- | --- | --- | --- | --- | --- | --- | --- | --- |\n|  | {} | Fix a\\|b \\*now\\* | 2026-01-02 | 2026-03-01 | ended | high | first<br>second | ops ui |  |\n",
+                task.get_id()
+            )
+        );
+    }
+
+    #[test]
+    fn test_checklist() {
+        let task = task();
+        let pending = ConcreteTaskBuilder::new()
+            .set_name(String::from("Deploy"))
+            .get_task();
+        let mut out = Vec::new();
+
+        write_checklist(&mut out, &[task.clone(), pending.clone()], &DEFAULT_COLUMNS).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "- [x] Fix a\\|b \\*now\\* (ID: {}, Modification: 2026-01-02, Term: 2026-03-01, Task State: ended, Priority: high, Tags: ops ui)\n  first\n  second\n- [ ] Deploy (ID: {}, Modification: {})\n",
+                task.get_id(),
+                pending.get_id(),
+                Date::get_local_date().to_iso_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_selected_columns() {
+        let task = task();
+        let mut out = Vec::new();
+
+        write_checklist(
+            &mut out,
+            std::slice::from_ref(&task),
+            &[ReportColumn::Name, ReportColumn::Term, ReportColumn::Age],
+        )
+        .unwrap();
+
+        let age = task
+            .modification()
+            .days_until(&Date::get_local_date())
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "- [x] Fix a\\|b \\*now\\* (Term: 2026-03-01, Age: {})\n",
+                age
+            )
+        );
+    }
+}
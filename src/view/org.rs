@@ -0,0 +1,133 @@
+use chrono::NaiveDate;
+use std::io::Write;
+
+use super::report_cells;
+use crate::model::error::TaskError;
+use crate::model::report_column::ReportColumn;
+use crate::{Date, Task, TaskState};
+
+// Org timestamps carry the day of the week, as in <2026-03-01 Sun>.
+fn timestamp(date: &Date) -> String {
+    return match NaiveDate::from_ymd_opt(date.year, date.month as u32, date.day as u32) {
+        Some(i) => format!("<{}>", i.format("%Y-%m-%d %a")),
+        None => format!("<{}>", date.to_iso_string()),
+    };
+}
+
+// Org tags cannot have a "-".
+fn org_tags(tags: &[String]) -> Vec<String> {
+    return tags.iter().map(|i| i.replace('-', "_")).collect();
+}
+
+fn property_name(header: &str) -> String {
+    return match header {
+        "#" => String::from("NUMBER"),
+        _ => header.to_uppercase().replace(' ', "_"),
+    };
+}
+
+// A headline per task: ended tasks are DONE and the others TODO, the term is
+// the DEADLINE, the priority a cookie from [#A] to [#D], the tags are the
+// ones of the headline and the remaining columns go in the property drawer.
+// The description is the body, indented so none of its lines can start a
+// headline.
+fn headline(task: &Task, columns: &[ReportColumn]) -> String {
+    let cells = report_cells(task, columns);
+    let shown = |column: ReportColumn| cells.iter().any(|i| i.column == column);
+
+    let keyword = if task.task_state() == Some(&TaskState::Ended) {
+        "DONE"
+    } else {
+        "TODO"
+    };
+    let mut lines = Vec::new();
+    let mut title = vec![String::from(keyword)];
+    if let (true, Some(i)) = (shown(ReportColumn::Priority), task.priority()) {
+        title.push(format!("[#{}]", i.to_letter()));
+    }
+    if shown(ReportColumn::Name) {
+        title.push(task.get_name().replace('\n', " "));
+    }
+    if shown(ReportColumn::Tags) && !task.tags().is_empty() {
+        title.push(format!(":{}:", org_tags(task.tags()).join(":")));
+    }
+    lines.push(format!("* {}", title.join(" ")));
+    if let (true, Some(i)) = (shown(ReportColumn::Term), task.term()) {
+        lines.push(format!("  DEADLINE: {}", timestamp(i)));
+    }
+
+    let properties = cells
+        .iter()
+        .filter(|i| !i.value.is_empty())
+        .filter(|i| {
+            !matches!(
+                i.column,
+                ReportColumn::Name
+                    | ReportColumn::Tags
+                    | ReportColumn::Term
+                    | ReportColumn::Priority
+                    | ReportColumn::Description
+            )
+        })
+        .map(|i| format!("  :{}: {}", property_name(i.header), i.value))
+        .collect::<Vec<String>>();
+    if !properties.is_empty() {
+        lines.push(String::from("  :PROPERTIES:"));
+        lines.extend(properties);
+        lines.push(String::from("  :END:"));
+    }
+
+    if let (true, Some(i)) = (shown(ReportColumn::Description), task.description()) {
+        lines.extend(i.lines().map(|j| format!("  {}", j)));
+    }
+    return lines.join("\n");
+}
+
+pub fn write_tasks<W: Write>(
+    out: &mut W,
+    tasks: &[Task],
+    columns: &[ReportColumn],
+) -> Result<(), TaskError> {
+    for i in tasks {
+        writeln!(out, "{}", headline(i, columns))?;
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests_org {
+    use super::*;
+    use crate::model::report_column::DEFAULT_COLUMNS;
+    use crate::{ConcreteTaskBuilder, Priority, TaskBuilder};
+
+    #[test]
+    fn test_headline() {
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Renew TLS"))
+            .set_tags(vec![String::from("ops"), String::from("tls-certs")])
+            .set_description(String::from("* not a headline\nsecond"))
+            .set_term(Date::from_iso_string("2026-03-01").unwrap())
+            .set_priority(Priority::Urgent)
+            .set_task_state(TaskState::InProgress)
+            .set_modification(Date::from_iso_string("2026-01-02").unwrap())
+            .get_task();
+
+        assert_eq!(
+            headline(&task, &DEFAULT_COLUMNS),
+            format!(
+                "* TODO [#A] Renew TLS :ops:tls_certs:\n  DEADLINE: <2026-03-01 Sun>\n  :PROPERTIES:\n  :ID: {}\n  :MODIFICATION: 2026-01-02\n  :TASK_STATE: in progress\n  :END:\n  * not a headline\n  second",
+                task.get_id()
+            )
+        );
+    }
+
+    #[test]
+    fn test_done_headline() {
+        let task = ConcreteTaskBuilder::new()
+            .set_name(String::from("Pay rent"))
+            .set_task_state(TaskState::Ended)
+            .get_task();
+
+        assert!(headline(&task, &DEFAULT_COLUMNS).starts_with("* DONE Pay rent\n  :PROPERTIES:"));
+    }
+}